@@ -1,3 +1,4 @@
+use aoclib::graph::{self, Cycle};
 use regex::Regex;
 use std::fs::File;
 use std::io::BufReader;
@@ -42,79 +43,39 @@ fn read_rules(filename: &str) -> Result<Rules, String> {
     Ok(rules)
 }
 
-enum VisitState {
-    NotSeen,
-    Visiting,
-    Seen
+fn contained_colors<'a>(rules: &'a Rules, color: &String) -> impl Iterator<Item = String> + 'a {
+    rules.get(color).unwrap().iter().map(|rule| rule.color.clone())
 }
 
-fn topologic_sort_<'a>(color: &'a str, rules: &'a Rules, state: &mut HashMap<&'a str, VisitState>, output: &mut Vec<String>) -> Option<()> {
-    let this_state = state.get(color).unwrap_or(&VisitState::NotSeen);
-    match this_state {
-        VisitState::Visiting => None,
-        VisitState::Seen => Some(()),
-        VisitState::NotSeen => {
-            state.insert(color, VisitState::Visiting);
-            for contained_rule in rules.get(color).unwrap() {
-                topologic_sort_(&contained_rule.color, rules, state, output)?;
-            }
-            state.insert(color, VisitState::Seen);
-            output.push(color.to_string());
-            Some(())
-        }
-    }
-}
-
-fn topologic_sort(rules: &Rules) -> Option<Vec<String>> {
-    let mut output: Vec<String> = Vec::new();
-    let mut state: HashMap<&str, VisitState> = HashMap::new();
-
-    for color in rules.keys() {
-        topologic_sort_(color, rules, &mut state, &mut output)?;
-    }
-
-    Some(output)
-}
-
-fn count_reachable(rules: &Rules, sorted_colors: &Vec<String>, target: &str) -> usize {
-    let mut count = 0;
-    let mut is_reachable: HashMap<&str, bool> = HashMap::new();
-    for color in sorted_colors {
-        let mut this_is_reachable = color == target;
-        for rule in rules.get(color).unwrap() {
-            let color2 = rule.color.as_ref();
-            this_is_reachable = this_is_reachable || *is_reachable.get(&color2).unwrap();
-        }
-        is_reachable.insert(&color, this_is_reachable);
-        if this_is_reachable {
-            println!("Could be in a {} bag", color);
-            count += 1;
-        }
+fn count_reachable(rules: &Rules, target: &str) -> usize {
+    let reachable = graph::reverse_reachable(
+        rules.keys().cloned(),
+        |color| contained_colors(rules, color),
+        &target.to_string(),
+    );
+    let mut colors: Vec<&String> = reachable.iter().collect();
+    colors.sort();
+    for color in colors {
+        println!("Could be in a {} bag", color);
     }
-    count
+    reachable.len()
 }
 
-fn count_bags_for(rules: &Rules, sorted_colors: &Vec<String>, target: &str) -> Option<usize> {
-    let mut counts: HashMap<&str, usize> = HashMap::new();
-    for color in sorted_colors {
-        let mut count: usize = 1;
-        for rule in rules.get(color).unwrap() {
-            let color2 = rule.color.as_ref();
-            count += rule.count * counts.get(&color2).unwrap();
-        }
-        counts.insert(&color, count);
-        if color == target {
-            return Some(count);
-        }
-    }
-    return None
+fn count_bags_for(rules: &Rules, target: &str) -> Result<usize, Cycle<String>> {
+    let counts = graph::fold_dag(
+        rules.keys().cloned(),
+        |color| contained_colors(rules, color),
+        |color, counts: &HashMap<String, usize>| {
+            1 + rules.get(color).unwrap().iter().map(|rule| rule.count * counts[&rule.color]).sum::<usize>()
+        },
+    )?;
+    Ok(counts[target])
 }
 
 fn main() {
     let rules = read_rules("input.txt").unwrap();
-    let sorted_colors = topologic_sort(&rules).unwrap();
-    let count = count_reachable(&rules, &sorted_colors, "shiny gold");
+    let count = count_reachable(&rules, "shiny gold");
     println!("Shiny gold could be in {} bags (not including self)", count - 1);
-    let bags_in_gold = count_bags_for(&rules, &sorted_colors, "shiny gold");
+    let bags_in_gold = count_bags_for(&rules, "shiny gold");
     println!("Shiny gold contains {} bags (not including self)", bags_in_gold.unwrap() - 1)
 }
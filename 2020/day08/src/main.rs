@@ -1,10 +1,12 @@
-use regex::Regex;
+use std::env;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
 use std::vec::Vec;
 use std::result::Result;
 
+use aoclib::vm::{self, Halt, Instruction, Machine, Repair};
+
 # [derive(Debug, Copy, Clone)]
 enum Op {
     Acc,
@@ -12,61 +14,61 @@ enum Op {
     Nop,
 }
 
+#[derive(Debug, Clone)]
 struct Instr {
     op: Op,
     arg: isize,
 }
 
-fn read_instrs(filename: &str) -> Result<Vec<Instr>, String> {
-    let f = File::open(filename).map_err(|e| e.to_string())?;
-    let br = BufReader::new(f);
-    let expr = Regex::new("(acc|jmp|nop) ([+-]\\d+)").unwrap();
-    let mut instrs: Vec<Instr> = Vec::new();
-    for line in br.lines() {
-        let line = line.map_err(|e| e.to_string())?;
-        let caps = expr.captures(&line).ok_or(format!("No match in line \"{}\"", line))?;
-        let op = match caps.get(1).unwrap().as_str() {
+/// The handheld console: the only state is the accumulator.
+impl Instruction for Instr {
+    type State = isize;
+
+    fn parse(line: &str) -> Result<Instr, String> {
+        let (op, arg) = line.split_once(' ').ok_or(format!("No match in line \"{}\"", line))?;
+        let op = match op {
             "acc" => Op::Acc,
             "jmp" => Op::Jmp,
             "nop" => Op::Nop,
             other => return Err(format!("Bad instruction kind \"{}\"", other)),
         };
-        let arg: isize = caps.get(2)
-                             .unwrap()
-                             .as_str()
-                             .parse()
-                             .map_err(|_| format!("bad arg in line \"{}\"", line))?;
-        instrs.push(Instr{op: op, arg: arg});
+        let arg: isize = arg.parse().map_err(|_| format!("bad arg in line \"{}\"", line))?;
+        Ok(Instr{op: op, arg: arg})
     }
-    Ok(instrs)
-}
-
-fn execute(instrs: &Vec<Instr>) -> Result<(isize, bool), String> {
-    let mut acc: isize = 0;
-    let mut idx: usize = 0;
-    let mut cnt: isize = 0;
-    let mut seen = vec![-1; instrs.len()];
 
-    while idx < instrs.len() && seen[idx] < 0 {
-        seen[idx] = cnt;
-        cnt += 1;
-        match instrs[idx].op {
+    fn execute(&self, acc: &mut isize) -> isize {
+        match self.op {
             Op::Acc => {
-                acc += instrs[idx].arg;
-                idx += 1;
-            }
-            Op::Jmp => {
-                let new_idx = (idx as isize) + instrs[idx].arg;
-                if new_idx < 0 || new_idx >= (instrs.len() as isize) + 1 {
-                    return Err(format!("Bad jump target {} from instr {}", new_idx, idx))
-                }
-                idx = new_idx as usize;
+                *acc += self.arg;
+                1
             }
-            Op::Nop => { idx += 1 },
+            Op::Jmp => self.arg,
+            Op::Nop => 1,
         }
     }
-    let infinite_loop = idx < instrs.len();
-    Ok((acc, infinite_loop))
+}
+
+fn read_instrs(filename: &str) -> Result<Vec<Instr>, String> {
+    let f = File::open(filename).map_err(|e| e.to_string())?;
+    let lines: Vec<String> = BufReader::new(f).lines().collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+    vm::parse_program(lines).map_err(|e| e.to_string())
+}
+
+/// Run the program, returning the final accumulator and whether it looped.
+fn execute(instrs: &Vec<Instr>) -> Result<(isize, bool), String> {
+    let mut machine = Machine::new(instrs, 0);
+    let halt = machine.run().map_err(|e| e.to_string())?;
+    Ok((*machine.state(), halt != Halt::Finished))
+}
+
+fn print_trace(instrs: &[Instr]) -> Result<(), String> {
+    let mut machine = Machine::new(instrs, 0).with_trace();
+    let halt = machine.run().map_err(|e| e.to_string())?;
+    for entry in machine.trace() {
+        println!("{:4}: {:?} {:+} -> acc {}", entry.pc, entry.instr.op, entry.instr.arg, entry.state);
+    }
+    println!("{:?}", halt);
+    Ok(())
 }
 
 fn exec_until_loop(instrs: &Vec<Instr>) -> Result<isize, String> {
@@ -78,26 +80,30 @@ fn exec_until_loop(instrs: &Vec<Instr>) -> Result<isize, String> {
     )
 }
 
-fn find_swapped_instr(instrs: &mut Vec<Instr>) -> Result<(isize, usize), String> {
-    for i in 0..instrs.len() {
-        let orig_op = instrs[i].op;
-        instrs[i].op = match instrs[i].op {
-            Op::Nop => Op::Jmp,
-            Op::Jmp => Op::Nop,
-            _ => continue
-        };
-        if let Ok((acc, false)) = execute(instrs) {
-            return Ok((acc, i));
-        }
-        instrs[i].op = orig_op;
-    }
-    Err("None worked".to_string())
+/// The corrupted instruction is a jmp which should be a nop or vice versa.
+fn swapped(instr: &Instr) -> Option<Instr> {
+    let op = match instr.op {
+        Op::Nop => Op::Jmp,
+        Op::Jmp => Op::Nop,
+        Op::Acc => return None,
+    };
+    Some(Instr{op, arg: instr.arg})
 }
 
+fn find_swapped_instr(instrs: &[Instr]) -> Result<Repair<Instr>, String> {
+    vm::repair(instrs, 0, swapped)
+        .map_err(|e| e.to_string())?
+        .ok_or("None worked".to_string())
+}
+
+/// Usage: day08 [--trace]
 fn main() {
-    let mut prog = read_instrs("input.txt").unwrap();
+    let prog = read_instrs("input.txt").unwrap();
+    if env::args().any(|a| a == "--trace") {
+        print_trace(&prog).unwrap();
+    }
     let acc1 = exec_until_loop(&prog).unwrap();
     println!("Accumulator {} before loop", acc1);
-    let (acc2, instr) = find_swapped_instr(&mut prog).unwrap();
-    println!("Accumulator {} after swapping instruction {}", acc2, instr);
+    let fix = find_swapped_instr(&prog).unwrap();
+    println!("Accumulator {} after swapping instruction {} to {:?} {:+}", fix.state, fix.pc, fix.instr.op, fix.instr.arg);
 }
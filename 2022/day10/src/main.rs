@@ -4,52 +4,77 @@ use std::error::Error;
 use std::result::Result;
 use std::env;
 
+use aoclib::vm::{self, Instruction, Machine};
+
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
-fn do_cycle(cycle: &mut usize, x: &isize, sigstrength: &mut isize) {
+#[derive(Clone, Copy, Debug)]
+enum Instr {
+    Noop,
+    Addx(isize),
+}
+
+/// The only state is the X register.
+impl Instruction for Instr {
+    type State = isize;
+
+    fn parse(line: &str) -> Result<Instr, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() == 1 && tokens[0] == "noop" {
+            Ok(Instr::Noop)
+        } else if tokens.len() == 2 && tokens[0] == "addx" {
+            let increment = isize::from_str_radix(tokens[1], 10).map_err(|e| e.to_string())?;
+            Ok(Instr::Addx(increment))
+        } else {
+            Err(format!("Bad input line: {}", line))
+        }
+    }
+
+    fn cycles(&self) -> usize {
+        match self {
+            Instr::Noop => 1,
+            Instr::Addx(_) => 2,
+        }
+    }
+
+    fn execute(&self, x: &mut isize) -> isize {
+        if let Instr::Addx(increment) = self {
+            *x += increment;
+        }
+        1
+    }
+}
+
+fn do_cycle(cycle: usize, x: isize, sigstrength: &mut isize) {
     //println!("During cycle {}, x={}", cycle, x);
-    if *cycle % 40 == 20 {
-        println!("Strength component, cycle {}: {}", cycle, (*cycle as isize) * *x);
-        *sigstrength += (*cycle as isize) * *x;
+    if cycle % 40 == 20 {
+        println!("Strength component, cycle {}: {}", cycle, (cycle as isize) * x);
+        *sigstrength += (cycle as isize) * x;
     }
-    *cycle += 1;
 }
 
-fn do_cycle_crt(cycle: &mut usize, x: &isize, _: &mut isize) {
-    let cur_pixel = ((*cycle - 1) as isize) % 40;
-    if cur_pixel == 0 && *cycle != 1 {
+fn do_cycle_crt(cycle: usize, x: isize, _: &mut isize) {
+    let cur_pixel = ((cycle - 1) as isize) % 40;
+    if cur_pixel == 0 && cycle != 1 {
         print!("\n");
     }
-    if *x - 1 <= cur_pixel && cur_pixel <= *x + 1 {
+    if x - 1 <= cur_pixel && cur_pixel <= x + 1 {
         print!("#");
     } else {
         print!(".");
     }
-    *cycle += 1;
 }
 
-fn read_instructions(filename: &str, func: fn(&mut usize, &isize, &mut isize)) -> MyResult<()> {
+fn read_instructions(filename: &str) -> MyResult<Vec<Instr>> {
     let file = File::open(filename)?;
     let reader = io::BufReader::new(file);
+    let lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
+    Ok(vm::parse_program(lines)?)
+}
 
-    let mut cycle: usize = 1;
-    let mut x: isize = 1;
+fn run_program(program: &[Instr], func: fn(usize, isize, &mut isize)) -> MyResult<()> {
     let mut sigstrength: isize = 0;
-
-    for line in reader.lines() {
-        let line = line?;
-        let tokens: Vec<&str> = line.split_whitespace().collect();
-        if tokens.len() == 1 && tokens[0] == "noop" {
-            func(&mut cycle, &x, &mut sigstrength);
-        } else if tokens.len() == 2 && tokens[0] == "addx" {
-            let increment = isize::from_str_radix(tokens[1], 10)?;
-            func(&mut cycle, &x, &mut sigstrength);
-            func(&mut cycle, &x, &mut sigstrength);
-            x += increment;
-        } else {
-            return Err(format!("Bad input line: {}", line).into());
-        }
-    }
+    Machine::new(program, 1).run_with(|cycle, x| func(cycle, *x, &mut sigstrength))?;
     println!("\nSignal strength: {}", sigstrength);
     Ok(())
 }
@@ -60,8 +85,9 @@ fn main() {
     if args.len() >= 2 {
         filename = &args[1];
     }
+    let program = read_instructions(filename).unwrap();
     println!("First, computing the signal strength:");
-    read_instructions(filename, do_cycle).unwrap();
+    run_program(&program, do_cycle).unwrap();
     println!("Second, printing the CRT:");
-    read_instructions(filename, do_cycle_crt).unwrap();
+    run_program(&program, do_cycle_crt).unwrap();
 }
@@ -3,6 +3,8 @@ use std::io::Read;
 use std::result::Result;
 use std::error::Error;
 use std::env;
+use aoclib::bigint::BigInt;
+use aoclib::math;
 use regex::{Regex, Match};
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
@@ -97,14 +99,15 @@ fn display_monkey_counts(monkeys: &Vec<Monkey>) {
     }
     let mut vals: Vec<usize> = monkeys.iter().map(|m| m.inspect_count).collect();
     vals.sort();
-    println!("Monkey business factor: {}", vals[vals.len() - 2] * vals[vals.len() - 1]);
+    let business: BigInt = vals[vals.len() - 2..].iter().map(|v| BigInt::from(*v)).product();
+    println!("Monkey business factor: {}", business);
 }
 
 fn do_monkey_round(monkeys: &mut Vec<Monkey>, round: u32, verbose: bool, worry_by_three: bool) {
-    let mut modulo: usize = 1;
-    for monkey in monkeys.iter() {
-        modulo *= monkey.test;
-    }
+    /* Every test only cares about worry levels modulo its divisor, so we can
+     * keep them bounded by reducing modulo the lcm of all the divisors. */
+    let modulo = math::lcm_all(monkeys.iter().map(|m| m.test as i64))
+        .expect("divisors' lcm overflows") as usize;
     for i in 0..monkeys.len() {
         let mut new_destinations: Vec<(Item, usize)> = Vec::new();
         for item in &monkeys[i].items {
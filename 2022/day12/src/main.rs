@@ -3,12 +3,13 @@ use std::fs::File;
 use std::io::{self, BufRead};
 use std::result::Result;
 use std::vec::Vec;
-use std::collections::VecDeque;
 use std::{thread,time};
 
+use aoclib::search::{self, SearchResult};
+
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 struct Coord {
     row: usize,
     col: usize,
@@ -96,21 +97,9 @@ impl<T> RectArray<T> {
     fn rows(&self) -> usize {
         self.arr.len() / self.cols
     }
-}
-
-impl<T> RectArray<T>
-   where T: Copy {
-    fn new(rows: usize, cols: usize, fill: T) -> RectArray<T> {
-        let arr: Vec<T> = (0..rows*cols).map(|_| fill).collect();
-        let empty = Coord{row: 0, col: 0};
-        RectArray{arr, cols, start: empty, end: empty}
-    }
     fn get(&self, c: Coord) -> &T {
         &self.arr[c.row * self.cols + c.col]
     }
-    fn set(&mut self, c: Coord, val: T) {
-        self.arr[c.row * self.cols + c.col] = val;
-    }
 }
 
 fn do_shortest_path<F1, F2>(
@@ -123,51 +112,63 @@ fn do_shortest_path<F1, F2>(
     where F1: Fn(u8, u8) -> bool,
           F2: Fn(Coord) -> bool,
 {
-    let mut q: VecDeque<Coord> = VecDeque::new();
-    let mut paths: RectArray<Option<usize>> = RectArray::new(topo.rows(), topo.cols, None);
-    paths.set(start, Some(0));
-    q.push_back(start);
+    let test = &test;
+    let res = search::bfs(
+        [start],
+        |cur| {
+            let height = *topo.get(*cur);
+            cur.adjacent(topo).filter(move |step| !test(*topo.get(*step), height))
+        },
+        |cur| term(*cur),
+    );
+    if print {
+        animate_search(topo, &res);
+        print_path_arr(topo, &res);
+    }
+    res.target_distance()
+}
 
-    let mut prev_len = 0;
-    while !q.is_empty() {
-        let cur = q.pop_front().unwrap();
-        let height = *topo.get(cur);
-        let len = paths.get(cur).unwrap();
-        if len != prev_len && print {
-            print_path_arr(&paths, len);
-            prev_len = len;
-        }
-        if term(cur) {
-            return Some(len)
-        }
-        for step in cur.adjacent(topo) {
-            if test(*topo.get(step), height) {
-                continue;
-            }
-            if let None = paths.get(step) {
-                paths.set(step, Some(len + 1));
-                q.push_back(step);
+/// Replay the search one distance at a time, marking the frontier with '#'
+/// and everything already behind it with '.'.
+fn animate_search(topo: &RectArray<u8>, res: &SearchResult<Coord, usize>) {
+    let last = res.target_distance().or_else(|| res.dist.values().max().copied()).unwrap_or(0);
+    for cur in 1..=last {
+        // I really don't want to deal with ncurses, this seems "good enough"
+        print!("\x1B[2J\x1B[1;1H");
+        for row in 0..topo.rows() {
+            for col in 0..topo.cols {
+                match res.distance(&Coord{row, col}) {
+                    None => { print!(" "); },
+                    Some(len) if len < cur => { print!("."); },
+                    Some(len) if len == cur => { print!("#"); },
+                    Some(_) => { print!(" "); },
+                }
             }
+            println!();
         }
+        println!();
+        thread::sleep(time::Duration::from_millis(50));
     }
-    None
 }
 
-fn print_path_arr(paths: &RectArray<Option<usize>>, cur: usize) {
-    // I really don't want to deal with ncurses, this seems "good enough"
+/// The final picture: the path found, and everywhere else the search went.
+fn print_path_arr(topo: &RectArray<u8>, res: &SearchResult<Coord, usize>) {
+    let path = res.target_path().unwrap_or_default();
     print!("\x1B[2J\x1B[1;1H");
-    for row in 0..paths.rows() {
-        for col in 0..paths.cols {
-            match paths.get(Coord{row, col}) {
-                None => { print!(" "); },
-                Some(len) if *len < cur => { print!("."); },
-                Some(_) => { print!("#"); }
+    for row in 0..topo.rows() {
+        for col in 0..topo.cols {
+            let coord = Coord{row, col};
+            if path.contains(&coord) {
+                print!("#");
+            } else if res.dist.contains_key(&coord) {
+                print!(".");
+            } else {
+                print!(" ");
             }
         }
         print!("\n");
     }
     print!("\n");
-    thread::sleep(time::Duration::from_millis(50));
 }
 
 fn main() {
@@ -1,6 +1,3 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::collections::VecDeque;
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -8,149 +5,163 @@ use std::io::{BufReader, BufRead};
 use std::result::Result;
 use std::vec::Vec;
 
+use aoclib::combinatorics::best_submasks;
+use aoclib::intern::Interner;
+
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
 #[derive(Clone)]
 struct Valve {
-    name: String,
     rate: u32,
-    tunnels: Vec<String>,
-    shortest_paths: HashMap<String, u32>,
+    tunnels: Vec<u32>,
 }
 
-fn read_valves(filename: &str) -> MyResult<HashMap<String, Valve>> {
+/// Read the valves, which are indexed by their interned name.
+fn read_valves(filename: &str) -> MyResult<(Interner, Vec<Valve>)> {
     let reader = BufReader::new(File::open(filename)?);
-    let mut res: HashMap<String, Valve> = HashMap::new();
+    let mut names = Interner::new();
+    let mut res: Vec<Option<Valve>> = Vec::new();
     for line in reader.lines() {
         let line = line?;
         let tokens: Vec<&str> = line.split_whitespace().collect();
-        let name = String::from(tokens[1]);
+        let id = names.intern(tokens[1]);
         let rate_str = &tokens[4];
         let rate_str = &rate_str[5..rate_str.len() - 1];
         let rate = u32::from_str_radix(rate_str, 10)?;
-        let mut tunnels: Vec<String> = Vec::new();
+        let mut tunnels: Vec<u32> = Vec::new();
         for tunnel in &tokens[9..] {
-            if tunnel.len() == 3 {
-                tunnels.push(String::from(&tunnel[..tunnel.len() - 1]));
-            } else {
-                tunnels.push(String::from(*tunnel));
-            }
+            tunnels.push(names.intern(tunnel.trim_end_matches(',')));
         }
-        res.insert(name.clone(), Valve{name, rate, tunnels, shortest_paths: HashMap::new()});
+        res.resize(names.len(), None);
+        res[id as usize] = Some(Valve{rate, tunnels});
     }
-    Ok(res)
+    let valves = res
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| v.ok_or_else(|| format!("valve {} is never described", names.name(i as u32))))
+        .collect::<Result<_, _>>()?;
+    Ok((names, valves))
 }
 
-fn do_shortest_path(start: &str, graph: &mut HashMap<String, Valve>) {
-    let mut path_lengths: HashMap<String, u32> = HashMap::new();
-    let mut queue: VecDeque<String> = VecDeque::new();
-    path_lengths.insert(String::from(start), 0);
-    queue.push_front(String::from(start));
+/// The most valves worth opening that we can handle: best_per_mask keeps a
+/// score and a route for every subset of them, 2^n of each.
+const MAX_USEFUL: usize = 20;
 
-    while !queue.is_empty() {
-        let name = queue.pop_back().unwrap();
-        let valve = graph.get(&name).unwrap();
-        let cur_path = *path_lengths.get(&name).unwrap();
-        let tunnels = valve.tunnels.clone();
-        for tunnel in tunnels.iter() {
-            if path_lengths.contains_key(tunnel) {
-                continue;
-            }
-            if let Some(_) = graph.get(tunnel) {
-                queue.push_front(tunnel.clone());
-                path_lengths.insert(tunnel.clone(), cur_path + 1);
-            } else {
-                panic!("No next line for {}", tunnel);
-            }
-        }
-    }
-    let valve = graph.get_mut(start).unwrap();
-    valve.shortest_paths = path_lengths;
+/// The valves worth opening (those with a non-zero rate), renumbered densely
+/// so that a set of them fits in a bitmask. `dist[i][j]` is the walking
+/// time from valve i to valve j, and the extra row at the end is the distance
+/// from AA, where we start.
+struct Cave {
+    names: Vec<String>,
+    rates: Vec<u32>,
+    dist: Vec<Vec<u32>>,
 }
 
-fn do_all_shortest_path(graph: &mut HashMap<String, Valve>) {
-    let names: Vec<String> = graph.keys().map(|v| String::from(v)).collect();
-    for name in names {
-        do_shortest_path(&name, graph);
+impl Cave {
+    /*
+     * Only valves with a non-zero rate are worth walking to, so contract the
+     * tunnel graph down to those (plus the starting valve) and keep the
+     * distances between them.
+     */
+    fn new(names: &Interner, valves: &[Valve]) -> MyResult<Cave> {
+        let aa = names.get("AA").ok_or("no valve AA")?;
+        let contracted = aoclib::graph::contract(
+            0..valves.len() as u32,
+            |id| valves[*id as usize].tunnels.clone(),
+            |id| *id == aa || valves[*id as usize].rate > 0,
+        );
+        let mut useful: Vec<usize> = (0..contracted.len())
+            .filter(|i| valves[contracted.nodes[*i] as usize].rate > 0)
+            .collect();
+        if useful.len() > MAX_USEFUL {
+            return Err(format!("too many valves worth opening: {} (at most {}, as every subset of them gets a table entry)",
+                               useful.len(), MAX_USEFUL).into());
+        }
+        useful.sort_by_key(|i| names.name(contracted.nodes[*i]));
+        let start = contracted.index[&aa];
+        let mut dist = Vec::new();
+        for from in useful.iter().chain(std::iter::once(&start)) {
+            let row = useful
+                .iter()
+                .map(|to| contracted.dist[*from][*to].map(|d| d as u32).unwrap_or(u32::MAX / 2))
+                .collect();
+            dist.push(row);
+        }
+        let ids: Vec<u32> = useful.iter().map(|i| contracted.nodes[*i]).collect();
+        let names = ids.iter().map(|id| names.name(*id).to_string()).collect();
+        let rates = ids.iter().map(|id| valves[*id as usize].rate).collect();
+        Ok(Cave{names, rates, dist})
     }
-}
 
-fn all_choices(start: &str, minutes_remain: u32, graph: &HashMap<String, Valve>, closed: &HashSet<String>) -> Vec<(String, u32, u32)> {
-    let mut vec: Vec<(String, u32, u32)> = Vec::new();
-    let start_valve = graph.get(start).unwrap();
+    fn len(&self) -> usize {
+        self.names.len()
+    }
 
-    for tunnel in graph.values() {
-        let path_len = *start_valve.shortest_paths.get(&tunnel.name).unwrap();
-        if path_len + 1 >= minutes_remain {
-            continue
-        }
-        if closed.contains(&tunnel.name) {
-            continue
-        }
-        if tunnel.rate == 0 {
-            continue
-        }
-        let score = (minutes_remain - path_len - 1) * tunnel.rate;
-        vec.push((tunnel.name.clone(), score, path_len));
+    fn route_names(&self, route: &[u8]) -> Vec<String> {
+        route.iter().map(|i| self.names[*i as usize].clone()).collect()
     }
-    vec
 }
 
-fn closed_set_string(closed: &HashSet<String>) -> String {
-    let mut v: Vec<&str> = closed.iter().map(|v| v.as_ref()).collect();
-    v.sort();
-    v.join("")
+/// For every set of valves (as a bitmask), the most pressure that can be
+/// released by opening exactly that set, and the route that does it.
+struct BestPerMask {
+    score: Vec<u32>,
+    route: Vec<Vec<u8>>,
 }
 
-fn best_opt(a: Option<(u32, Vec<String>)>, b: Option<(u32, Vec<String>)>) -> Option<(u32, Vec<String>)> {
-    if let (Some(ta), Some(tb)) = (&a, &b) {
-        if ta.0 >= tb.0 {
-            a
-        } else {
-            b
+/*
+ * Walk every route from AA, one valve at a time, for as long as there's time
+ * left to get somewhere and open it. The state at each step is (position,
+ * time left, opened mask), and the best score for each mask is recorded as we
+ * go. That's all we need for both parts: part 1 is the best mask overall, and
+ * part 2 is the best pair of disjoint masks (one for us, one for the
+ * elephant).
+ */
+fn best_per_mask(cave: &Cave, minutes: u32) -> BestPerMask {
+    fn visit(cave: &Cave, pos: usize, time: u32, mask: u64, score: u32, route: &mut Vec<u8>, best: &mut BestPerMask) {
+        for next in 0..cave.len() {
+            let bit = 1u64 << next;
+            let cost = cave.dist[pos][next] + 1;
+            if mask & bit != 0 || cost >= time {
+                continue;
+            }
+            let time = time - cost;
+            let score = score + time * cave.rates[next];
+            route.push(next as u8);
+            let m = (mask | bit) as usize;
+            if score > best.score[m] {
+                best.score[m] = score;
+                best.route[m] = route.clone();
+            }
+            visit(cave, next, time, mask | bit, score, route, best);
+            route.pop();
         }
-    } else {
-        a.or(b)
-    }
-}
-
-fn best_choice_rec(start: &str, minutes_remain: u32, graph: &HashMap<String, Valve>, closed: &HashSet<String>,
-                   memoize: &mut HashMap<(String, String, u32, u32), (u32, Vec<String>)>, tries: u32) -> (u32, Vec<String>) {
-    let mut best: Option<(u32, Vec<String>)> = None;
-    let key = (closed_set_string(closed), String::from(start), minutes_remain, tries);
-    if let Some(val) = memoize.get(&key) {
-        return val.clone();
-    }
-    for (next, max_score, minutes) in all_choices(start, minutes_remain, graph, closed) {
-        let next_rem = minutes_remain - 1 - minutes;
-        let mut closed_next = closed.clone();
-        closed_next.insert(next.clone());
-        let ( mut score, mut moves) = best_choice_rec(&next, next_rem, graph, &closed_next, memoize, tries);
-        score += max_score;
-        moves.insert(0, next.clone());
-        best = best_opt(best, Some((score, moves)));
-    }
-    if tries > 0 {
-        best = best_opt(
-            best,
-            Some(best_choice_rec("AA", 26, graph, closed, memoize, tries - 1)),
-        )
     }
-    let best = best.unwrap_or((0, Vec::new()));
-    memoize.insert(key, best.clone());
+    let size = 1 << cave.len();
+    let mut best = BestPerMask{score: vec![0; size], route: vec![Vec::new(); size]};
+    visit(cave, cave.len(), minutes, 0, 0, &mut Vec::new(), &mut best);
     best
 }
 
-fn best_choice(graph: &HashMap<String, Valve>) -> (u32, Vec<String>) {
-    let closed: HashSet<String> = HashSet::new();
-    let mut memoize: HashMap<(String, String, u32, u32), (u32, Vec<String>)> = HashMap::new();
-    best_choice_rec("AA", 30, graph, &closed, &mut memoize, 0)
+fn best_choice(cave: &Cave) -> (u32, Vec<String>) {
+    let best = best_per_mask(cave, 30);
+    let m = (0..best.score.len()).max_by_key(|m| best.score[*m]).unwrap();
+    (best.score[m], cave.route_names(&best.route[m]))
 }
 
-fn best_choice_with_elephant(graph: &HashMap<String, Valve>) -> (u32, Vec<String>) {
-    let closed: HashSet<String> = HashSet::new();
-    let mut memoize: HashMap<(String, String, u32, u32), (u32, Vec<String>)> = HashMap::new();
-    best_choice_rec("AA", 26, graph, &closed, &mut memoize, 1)
+fn best_choice_with_elephant(cave: &Cave) -> (u32, Vec<String>) {
+    let best = best_per_mask(cave, 26);
+    /* The elephant doesn't have to open every valve we leave alone, so
+     * it takes the best submask of whatever we don't open. */
+    let sub = best_submasks(&best.score);
+    let full = best.score.len() - 1;
+    let (ours, theirs) = (0..best.score.len())
+        .map(|m| (m, sub[full ^ m]))
+        .max_by_key(|(a, b)| best.score[*a] + best.score[*b])
+        .unwrap();
+    let mut sequence = cave.route_names(&best.route[ours]);
+    sequence.extend(cave.route_names(&best.route[theirs]));
+    (best.score[ours] + best.score[theirs], sequence)
 }
 
 fn main() {
@@ -159,13 +170,13 @@ fn main() {
     if args.len() >= 2 {
         filename = &args[1];
     }
-    let mut valves = read_valves(filename).unwrap();
-    do_all_shortest_path(&mut valves);
-    let (score, sequence) = best_choice(&valves);
+    let (names, valves) = read_valves(filename).unwrap();
+    let cave = Cave::new(&names, &valves).unwrap();
+    let (score, sequence) = best_choice(&cave);
     println!("Max score: {}", score);
     println!("Sequence: {:?}", sequence);
 
-    let (score, sequence) = best_choice_with_elephant(&valves);
+    let (score, sequence) = best_choice_with_elephant(&cave);
     println!("W/ Elephant, Max Score: {}", score);
     println!("W/ Elephant, Sequence: {:?}", sequence);
 }
@@ -3,8 +3,10 @@ use std::error::Error;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::result::Result;
+use std::thread;
 use std::vec::Vec;
 
+use aoclib::optimize::{self, Problem, Stats};
 use regex::Regex;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
@@ -43,7 +45,7 @@ fn read_blueprints(filename: &str) -> MyResult<Vec<Blueprint>> {
     Ok(res)
 }
 
-#[derive(Clone, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 struct State {
     minute: u32,
     ore: u32,
@@ -64,91 +66,116 @@ fn do_bot_production(state: &mut State) {
     state.minute -= 1;
 }
 
-fn maximize_geodes_rec(bp: &Blueprint, bpc: &BlueprintCorollary, state: &State) -> u32 {
-    let mut best: Option<u32> = None;
-
-    if state.ore_bots > 0 && state.ore_bots < bpc.max_ore_bots {
-        let mut state = state.clone();
-        while state.minute > 0 {
-            if state.ore >= bp.ore_bot_cost_ore {
-                do_bot_production(&mut state);
-                state.ore_bots += 1;
-                state.ore -= bp.ore_bot_cost_ore;
-                let opt = maximize_geodes_rec(bp, bpc, &state);
-                best = Some(best.map_or(opt, |b| b.max(opt)));
-                break;
-            } else {
-                do_bot_production(&mut state);
-            }
+/// Wait until `can_build` says we have the resources, then build the bot. None
+/// if we'd run out of time first.
+fn wait_and_build<C, B>(state: &State, can_build: C, build: B) -> Option<State>
+where C: Fn(&State) -> bool,
+      B: Fn(&mut State) {
+    let mut state = state.clone();
+    while state.minute > 0 {
+        if can_build(&state) {
+            do_bot_production(&mut state);
+            build(&mut state);
+            return Some(state);
         }
+        do_bot_production(&mut state);
     }
-    if state.ore_bots > 0 && state.clay_bots < bpc.max_clay_bots {
-        let mut state = state.clone();
-        while state.minute > 0 {
-            if state.ore >= bp.clay_bot_cost_ore {
-                do_bot_production(&mut state);
-                state.clay_bots += 1;
-                state.ore -= bp.clay_bot_cost_ore;
-                let opt = maximize_geodes_rec(bp, bpc, &state);
-                best = Some(best.map_or(opt, |b| b.max(opt)));
-                break;
-            } else {
-                do_bot_production(&mut state);
-            }
-        }
+    None
+}
+
+/// The branching is on which bot to build next (rather than what to do each
+/// minute), and there's no point building more of a bot than we could spend in
+/// one minute, which is what the BlueprintCorollary limits are for.
+struct Factory<'a> {
+    bp: &'a Blueprint,
+    bpc: BlueprintCorollary,
+}
+
+impl<'a> Factory<'a> {
+    fn new(bp: &'a Blueprint) -> Factory<'a> {
+        let bpc = BlueprintCorollary{
+            max_ore_bots: bp.clay_bot_cost_ore.max(bp.obsidian_bot_cost_ore.max(bp.geode_bot_cost_ore)),
+            max_clay_bots: bp.obsidian_bot_cost_clay,
+            max_obsidian_bots: bp.geode_bot_cost_obsidian,
+        };
+        Factory{bp, bpc}
     }
-    if state.ore_bots > 0 && state.clay_bots > 0 && state.obsidian_bots < bpc.max_obsidian_bots {
-        let mut state = state.clone();
-        while state.minute > 0 {
-            if state.ore >= bp.obsidian_bot_cost_ore && state.clay >= bp.obsidian_bot_cost_clay {
-                do_bot_production(&mut state);
-                state.obsidian_bots += 1;
-                state.ore -= bp.obsidian_bot_cost_ore;
-                state.clay -= bp.obsidian_bot_cost_clay;
-                let opt = maximize_geodes_rec(bp, bpc, &state);
-                best = Some(best.map_or(opt, |b| b.max(opt)));
-                break;
-            } else {
-                do_bot_production(&mut state);
-            }
+}
+
+impl Problem for Factory<'_> {
+    type State = State;
+    type Key = State;
+
+    fn successors(&self, state: &State) -> Vec<State> {
+        let (bp, bpc) = (self.bp, &self.bpc);
+        let mut next = Vec::new();
+        /* Geode bots first, since they're most likely to lead to a good score
+         * early on, which lets us prune more. */
+        if state.ore_bots > 0 && state.obsidian_bots > 0 {
+            next.extend(wait_and_build(
+                state,
+                |s| s.ore >= bp.geode_bot_cost_ore && s.obsidian >= bp.geode_bot_cost_obsidian,
+                |s| {
+                    s.geode_bots += 1;
+                    s.ore -= bp.geode_bot_cost_ore;
+                    s.obsidian -= bp.geode_bot_cost_obsidian;
+                },
+            ));
         }
-    }
-    if state.ore_bots > 0 && state.obsidian_bots > 0 {
-        let mut state = state.clone();
-        while state.minute > 0 {
-            if state.ore >= bp.geode_bot_cost_ore && state.obsidian >= bp.geode_bot_cost_obsidian {
-                do_bot_production(&mut state);
-                state.geode_bots += 1;
-                state.ore -= bp.geode_bot_cost_ore;
-                state.obsidian -= bp.geode_bot_cost_obsidian;
-                let opt = maximize_geodes_rec(bp, bpc, &state);
-                best = Some(best.map_or(opt, |b| b.max(opt)));
-                break;
-            } else {
-                do_bot_production(&mut state);
-            }
+        if state.ore_bots > 0 && state.clay_bots > 0 && state.obsidian_bots < bpc.max_obsidian_bots {
+            next.extend(wait_and_build(
+                state,
+                |s| s.ore >= bp.obsidian_bot_cost_ore && s.clay >= bp.obsidian_bot_cost_clay,
+                |s| {
+                    s.obsidian_bots += 1;
+                    s.ore -= bp.obsidian_bot_cost_ore;
+                    s.clay -= bp.obsidian_bot_cost_clay;
+                },
+            ));
+        }
+        if state.ore_bots > 0 && state.clay_bots < bpc.max_clay_bots {
+            next.extend(wait_and_build(
+                state,
+                |s| s.ore >= bp.clay_bot_cost_ore,
+                |s| {
+                    s.clay_bots += 1;
+                    s.ore -= bp.clay_bot_cost_ore;
+                },
+            ));
+        }
+        if state.ore_bots > 0 && state.ore_bots < bpc.max_ore_bots {
+            next.extend(wait_and_build(
+                state,
+                |s| s.ore >= bp.ore_bot_cost_ore,
+                |s| {
+                    s.ore_bots += 1;
+                    s.ore -= bp.ore_bot_cost_ore;
+                },
+            ));
         }
+        next
     }
 
-    match best {
-        Some(v) => v,
-        None => {
-            let mut state = state.clone();
-            while state.minute > 0 {
-                do_bot_production(&mut state);
-            }
-            state.geode
-        }
+    /// Geodes we'd end up with if we built nothing more.
+    fn score(&self, state: &State) -> i64 {
+        (state.geode + state.geode_bots * state.minute) as i64
+    }
+
+    /// Best case: a new geode bot every remaining minute.
+    fn upper_bound(&self, state: &State) -> i64 {
+        let m = state.minute as i64;
+        self.score(state) + m * (m - 1) / 2
+    }
+
+    /// Geodes already cracked don't change what happens next, so states which
+    /// differ only in those can share a memo entry.
+    fn key(&self, state: &State) -> Option<State> {
+        Some(State{geode: 0, ..state.clone()})
     }
 }
 
-fn maximize_geodes(bp: &Blueprint, minutes: u32) -> u32 {
-    let bpc = BlueprintCorollary{
-        max_ore_bots: bp.clay_bot_cost_ore.max(bp.obsidian_bot_cost_ore.max(bp.geode_bot_cost_ore)),
-        max_clay_bots: bp.obsidian_bot_cost_clay,
-        max_obsidian_bots: bp.geode_bot_cost_obsidian,
-    };
-    maximize_geodes_rec(bp, &bpc, &State{
+fn start_state(minutes: u32) -> State {
+    State{
         minute: minutes,
         ore: 0,
         clay: 0,
@@ -158,20 +185,37 @@ fn maximize_geodes(bp: &Blueprint, minutes: u32) -> u32 {
         clay_bots: 0,
         obsidian_bots: 0,
         geode_bots: 0,
-    })
+    }
+}
+
+/// Find the max geodes for each blueprint, solving blueprints in parallel.
+fn maximize_geodes(bps: &[Blueprint], minutes: u32, verbose: bool) -> Vec<u32> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let problems: Vec<_> = bps.iter().map(|bp| (Factory::new(bp), start_state(minutes))).collect();
+    let solutions = optimize::maximize_all(&problems, threads);
+    let mut stats = Stats::default();
+    for sol in solutions.iter() {
+        stats += sol.stats;
+    }
+    if verbose {
+        println!("{} minutes: explored {} states, pruned {}, memo hits {}",
+                 minutes, stats.explored, stats.pruned, stats.memo_hits);
+    }
+    solutions.iter().map(|sol| sol.score as u32).collect()
 }
 
 fn main() {
     let mut filename = "input.txt";
+    let mut verbose = false;
     let args: Vec<_> = env::args().collect();
     if args.len() >= 2 {
         filename = &args[1];
+        verbose = true;
     }
     let bps = read_blueprints(filename).unwrap();
     let mut total_quality = 0;
-    for (i, bp) in bps.iter().enumerate() {
+    for (i, geodes) in maximize_geodes(&bps, 24, verbose).into_iter().enumerate() {
         let i = i + 1;
-        let geodes = maximize_geodes(bp, 24);
         let quality = i * geodes as usize;
         total_quality += quality;
         println!("[{}]: max geodes (24min): {} quality: {}", i, geodes, quality);
@@ -179,8 +223,7 @@ fn main() {
     println!("Total quality: {}", total_quality);
 
     let mut product = 1;
-    for i in 0..3 {
-        let geodes = maximize_geodes(&bps[i], 32);
+    for (i, geodes) in maximize_geodes(&bps[..3.min(bps.len())], 32, verbose).into_iter().enumerate() {
         println!("[{}]: max geodes (32min): {}", i + 1, geodes);
         product *= geodes;
     }
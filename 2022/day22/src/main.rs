@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -7,6 +6,7 @@ use std::iter;
 use std::ops::{Add, Sub};
 use std::result::Result;
 
+use aoclib::cube::CubeNet;
 use regex::Regex;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
@@ -15,9 +15,7 @@ type MyResult<T> = Result<T, Box<dyn Error>>;
 /// notation here rather than (row, column), but also Y increases as you go
 /// down. Don't ask me what I was thinking.
 ///
-/// As is usual for my coordinates, they can be used for addition/subtraction,
-/// but also I added a multiply function to help with stepping over great
-/// distances.
+/// As is usual for my coordinates, they can be used for addition/subtraction.
 #[derive(Clone, Copy, Hash, Debug, PartialEq, Eq)]
 struct C2D(isize, isize);
 
@@ -37,12 +35,6 @@ impl Sub for C2D {
     }
 }
 
-impl C2D {
-    fn multiply(&self, val: isize) -> C2D {
-        C2D(self.0 * val, self.1 * val)
-    }
-}
-
 /// FACINGS are the directions you could be facing while traversing the map.
 /// This is directly from the problem description.
 const FACINGS: &[C2D] = &[
@@ -62,24 +54,16 @@ enum Cell {
     Wall,
 }
 
-#[derive(Clone, Copy)]
-enum StepStrategy {
-    Flat,
-    Cube,
-}
-
 /// Map - this is some variation on my somewhat "standard" map structure, where
 /// you have a small finite boundary indexed in two dimensions. It uses the X, Y
-/// method of indexing, with Y increasing as you go down. The map also stores
-/// the edge size of the cube - this is the main difference compared to other
-/// versions. The map doesn't contain too much in the way of cube logic, the
-/// idea is to keep that separate.
+/// method of indexing, with Y increasing as you go down. The map doesn't know
+/// anything about what happens when you walk off the edge, that's up to the
+/// WrapStrategy.
 struct Map {
     arr: Vec<Cell>,
     width: usize,
     height: usize,
     start: C2D,
-    edgesize: isize,
 }
 
 impl Map {
@@ -93,9 +77,8 @@ impl Map {
         let lines: Vec<_> = s.lines().collect();
         let width = lines.iter().map(|s| s.len()).max().unwrap();
         let height = lines.len();
-        let arr: Vec<Cell> = iter::repeat(Cell::Offmap).take(width * height).collect();
-        let mut map = Map{arr, width, height, start: C2D(0, 0), edgesize: 0};
-        let mut count_spaces = 0;
+        let mut arr: Vec<Cell> = iter::repeat(Cell::Offmap).take(width * height).collect();
+        let mut start = C2D(0, 0);
         let mut first = true;
         for (y, line) in lines.iter().enumerate() {
             for (i, c) in line.chars().enumerate() {
@@ -105,23 +88,14 @@ impl Map {
                     '#' => Cell::Wall,
                     _ => return Err("invalid char".into()),
                 };
-                count_spaces += 1;
-                let coord = C2D(i as isize, y as isize);
                 if first && cell == Cell::Open {
-                    map.start = coord;
+                    start = C2D(i as isize, y as isize);
                     first = false;
                 }
-                let ix = map.ix(&coord);
-                map.arr[ix] = cell;
+                arr[i + y * width] = cell;
             }
         }
-        /* We now need to determine this cube's edge size. */
-        let edge_size = ((count_spaces / 6) as f64).sqrt() as isize;
-        if edge_size * edge_size * 6 != count_spaces {
-            return Err("This map is not a cube!".into())
-        }
-        map.edgesize = edge_size;
-        Ok(map)
+        Ok(Map{arr, width, height, start})
     }
     fn get(&self, coord: &C2D) -> Cell {
         let ix = self.ix(coord);
@@ -130,6 +104,9 @@ impl Map {
     fn in_bounds(&self, coord: &C2D) -> bool {
         (0 <= coord.0) && (coord.0 < self.width as isize) && (0 <= coord.1) && (coord.1 < self.height as isize)
     }
+    fn on_map(&self, coord: &C2D) -> bool {
+        self.in_bounds(coord) && self.get(coord) != Cell::Offmap
+    }
     fn print(&self, pos: &C2D) {
         for y in 0..self.height {
             for x in 0..self.width {
@@ -152,236 +129,124 @@ impl Map {
             print!("\n");
         }
     }
-}
-
-/*
- * CUBE GEOMETRY. Like, fuck me, this is hard. But here we go.
- *
- *            9
- *    +----------------+
- *    |\8            5/|
- *    | +------------+ |
- *    | |     1      | |
- * 12 | |4          2| | 10
- *    | |     3      | |
- *    | +------------+ |
- *    |/7            6\|
- *    +----------------+
- *            11
- *
- * Ok, so imagine that the inner square is the surface of a cube, and it is
- * sitting on a piece of paper. The outer square is the surface of the cube
- * facing you.
- *
- * Now imagine rolling this cube upward (north). The side which was on the paper
- * is now on the downward side (south). Or more importantly, edge #1 has become
- * edge #3, and so on. Rolling the cube downward (south) produces the opposite
- * translation of edges. Rolling the cube left and right similarly produces a
- * set of complementary edge transitions.
- *
- * Now also imagine that we measure an "offset" which is the location along the
- * edge: the offset is measured in distance from the top (for vertical edges),
- * distance from the leftmost side (for horizontal edges), or distance from the
- * side closest from the paper (for edges on the axis we that are looking down).
- * Some transitions will require that the offset be "negated" to conform to the
- * new perspective.
- *
- * Below, we have two sets of translations. The first is for up/down and the
- * second is for right/left. The translations are represented as tuples:
- * (original edge, new edge, needs_negation). They are written in one direction
- * only, but the reverse direction is (new edge, original edge, needs_negation).
- * So they can be used in both situations.
- *
- * With these translations, we can essentially move along the unfolded cube, and
- * keep track of where we are on it. Once we are back on a edge which is on the
- * paper (i.e. edge 1-4), we can use our offset information to compute our new
- * coordinate. This is how we're going to continue across cube edges.
- *
- * And can I just say? Wow, this is hard. I'm kicking myself because I'm
- * absolutely certain that there's some sort of simple, clever rule that could
- * do all of this in just a few lines of code, but here I am writing a 50 line
- * comment to describe several arrays of data used in some real shitty code. But
- * you know what? It's the last star I need to complete this challenge, and
- * goddamn it, I will complete this challenge.
- */
-const UP_DOWN_TRANS: [(u8, u8, bool); 12] = [
-    (1, 3, false),
-    (3, 11, false),
-    (11, 9, false),
-    (9, 1, false),
-    (6, 10, true),
-    (10, 5, false),
-    (5, 2, true),
-    (2, 6, false),
-    (7, 12, true),
-    (12, 8, false),
-    (8, 4, true),
-    (4, 7, false),
-];
-
-const RIGHT_LEFT_TRANS: [(u8, u8, bool); 12] = [
-    (2, 4, false),
-    (4, 12, false),
-    (12, 10, false),
-    (10, 2, false),
-    (1, 8, true),
-    (8, 9, false),
-    (9, 5, true),
-    (5, 1, false),
-    (3, 7, true),
-    (7, 11, false),
-    (11, 6, true),
-    (6, 3, false),
-];
-
-/// Translate from a "facing" (which is an index into the FACINGS array and also
-/// a number used in the problem description) into an edge number.
-///
-/// This is intended be used when we're leaving one face of the cube. The
-/// direction we're facing corresponds to the edge we must currently be at.
-fn facing_to_edge(facing: usize) -> u8 {
-    match facing {
-        0 => 2, /* facing right: edge 2 */
-        1 => 3, /* facing down: edge 3 */
-        2 => 4, /* facing left: edge 4 */
-        3 => 1, /* facing up, edge 1 */
-        _ => panic!("Bad facing value {}", facing)
+    /// Draw the map with a traced path over it, like the problem description
+    /// does: each visited cell shows the last facing we had there.
+    fn render_trace(&self, trace: &[(C2D, usize)]) -> String {
+        let mut grid: Vec<char> = self.arr.iter().map(|c| match c {
+            Cell::Offmap => ' ',
+            Cell::Open => '.',
+            Cell::Wall => '#',
+        }).collect();
+        for (coord, facing) in trace {
+            let ix = self.ix(coord);
+            grid[ix] = ['>', 'v', '<', '^'][*facing];
+        }
+        let mut out = String::new();
+        for row in grid.chunks(self.width) {
+            out.extend(row.iter());
+            out.truncate(out.trim_end().len());
+            out.push('\n');
+        }
+        out
     }
 }
 
-/// Translate from an edge number to the corresponding facing. As opposed to the
-/// above function, this is actually a different mapping, because it is designed
-/// to be used for the case where you are *entering* a new cube face. Entering a
-/// cube face while facing right means that you are entering the left side, thus
-/// the difference.
-fn edge_to_facing(edge: u8) -> usize {
-    match edge {
-        2 => 2,
-        3 => 3,
-        4 => 0,
-        1 => 1,
-        _ => panic!("Bad edge value {}", edge)
-    }
+/// A WrapStrategy decides what happens when a step would take us off the map.
+/// Given the coordinate we're leaving from and the direction we're facing, it
+/// returns where we end up and which way we're facing afterward, or None if
+/// there's no way through (in which case the edge acts like a wall).
+trait WrapStrategy {
+    fn wrap(&self, map: &Map, coord: C2D, dir: usize) -> Option<(C2D, usize)>;
 }
 
-/// Given an edge on the bottom face of the cube, and a coordinate, return the
-/// offset. The offset is what we'll actually keep track of as we roll the cube
-/// around in search of the new coordinate.
-fn edge_offset(edge: u8, coord: C2D, edgesize: isize) -> isize {
-    /* Only valid for edges 1-4, convert the coordinate to an offset given an
-     * edge. */
-    if edge % 2 == 1 {
-        // top or bottom: use X coordinate % EDGSIZE
-        coord.0 % edgesize
-    } else {
-        coord.1 % edgesize
+/// Step off the map, assuming it's flat, like pac-man. You just need to
+/// backtrack to the other side of the map.
+struct Flat;
+
+impl WrapStrategy for Flat {
+    fn wrap(&self, map: &Map, coord: C2D, dir: usize) -> Option<(C2D, usize)> {
+        let mut bt = coord;
+        while map.on_map(&(bt - FACINGS[dir])) {
+            bt = bt - FACINGS[dir];
+        }
+        Some((bt, dir))
     }
 }
 
-/// Given an edge and a direction we're rolling the cube, return the new edge
-/// number and a flag true if we need to negate the offset.
-fn edge_transition(edge: u8, roll: usize) -> (u8, bool) {
-    let reg = !(roll == 1 || roll == 2);
-    let arr = if roll % 2 == 1 { UP_DOWN_TRANS } else { RIGHT_LEFT_TRANS };
+/// Keep going in the same direction, wrapping around the edges of the whole
+/// rectangle, until we land back on the map. The difference from Flat is in
+/// rows or columns with gaps: Flat always comes back around to the far end of
+/// the piece we're on, this jumps the gap.
+struct Torus;
 
-    for tup in arr {
-        if reg && tup.0 == edge {
-            return (tup.1, tup.2)
-        } else if !reg && tup.1 == edge {
-            return (tup.0, tup.2)
+impl WrapStrategy for Torus {
+    fn wrap(&self, map: &Map, coord: C2D, dir: usize) -> Option<(C2D, usize)> {
+        let (w, h) = (map.width as isize, map.height as isize);
+        let mut cur = coord;
+        loop {
+            let next = cur + FACINGS[dir];
+            cur = C2D(next.0.rem_euclid(w), next.1.rem_euclid(h));
+            if map.on_map(&cur) {
+                return Some((cur, dir));
+            }
         }
     }
-    panic!("Bad edge in transition")
 }
 
-/// Step off the map, assuming it's a cube. This implements one possible "step
-/// strategy". See the above CUBE GEOMETRY comment for a full description of
-/// what edge numbers are, how offset is defined, and the transition
-/// definitions.
-///
-/// The algorithm is basically a BFS. We determine which edge of the cube we're
-/// on, and then "roll" the cube around until we find a new section of the map
-/// where the same edge is also on the bottom. Then we make the necessary
-/// translation back into a coordinate and facing direction.
-fn step_cube(map: &Map, coord: C2D, facing: usize) -> (C2D, usize) {
-    let edge = facing_to_edge(facing);
-    let edgesize = map.edgesize;
-    let offset = edge_offset(edge, coord, edgesize);
-    let mut explore: Vec<(C2D, u8, isize)> = Vec::new();
-    let mut seen: HashSet<C2D> = HashSet::new();
-    explore.push((coord, edge, offset));
+/// Step off the map, assuming it's a cube. The net is folded up once when this
+/// is created (see aoclib::cube), so any of the unfolded layouts work, not just
+/// the one in my input.
+struct Cube {
+    net: CubeNet,
+}
 
-    while !explore.is_empty() {
-        let (this_coord, edge, offset) = explore.pop().unwrap();
-        seen.insert(this_coord);
-        for dir in 0..FACINGS.len() {
-            let new_coord = this_coord + FACINGS[dir].multiply(edgesize);
-            if !map.in_bounds(&new_coord) {
-                continue;
-            }
-            if let Cell::Offmap = map.get(&new_coord) {
-                continue;
-            }
-            if new_coord == coord {
-                continue;
-            }
-            // This is on the cube map, but would the transition leave us on an
-            // edge which is in contact with the "paper"?
-            let (new_edge, negate) = edge_transition(edge, dir);
-            let new_offset = if negate { edgesize - 1 - offset } else { offset };
-            if new_edge <= 4 {
-                // Yay, we found the new square, we just need to convert back to
-                // a coordinate and facing.
-                let b = C2D(new_coord.0 - new_coord.0 % edgesize, new_coord.1 - new_coord.1 % edgesize);
-                let new_facing = edge_to_facing(new_edge);
-                let real_coord = match new_edge {
-                    1 => C2D(b.0 + new_offset, b.1),
-                    2 => C2D(b.0 + edgesize - 1, b.1 + new_offset),
-                    3 => C2D(b.0 + new_offset, b.1 + edgesize - 1),
-                    4 => C2D(b.0, b.1 + new_offset),
-                    _ => panic!("Bad edge"),
-                };
-                return (real_coord, new_facing)
-            }
-            // Ok, this isn't the right location, keep searching
-            //println!("    Not on the bottom edge, continuing");
-            if !seen.contains(&new_coord) {
-                explore.push((new_coord, new_edge, new_offset));
-            }
+impl Cube {
+    fn new(map: &Map) -> MyResult<Cube> {
+        /* We need to determine this cube's edge size. */
+        let count = map.arr.iter().filter(|c| **c != Cell::Offmap).count();
+        let edge_size = ((count / 6) as f64).sqrt() as usize;
+        if edge_size * edge_size * 6 != count {
+            return Err("This map is not a cube!".into())
         }
+        let net = CubeNet::new(map.width, map.height, edge_size, |x, y| {
+            map.on_map(&C2D(x as isize, y as isize))
+        })?;
+        Ok(Cube{net})
     }
-    panic!("Could not find next coordinate!")
 }
 
-/// Step off the map, assuming it's flat, like pac-man. You just need to
-/// backtrack to the other side of the map.
-fn step_flat(map: &Map, coord: C2D, dir: usize) -> (C2D, usize) {
-    let mut bt = coord;
-    while map.in_bounds(&(bt - FACINGS[dir])) && map.get(&(bt - FACINGS[dir])) != Cell::Offmap {
-        bt = bt - FACINGS[dir];
+impl WrapStrategy for Cube {
+    fn wrap(&self, _map: &Map, coord: C2D, dir: usize) -> Option<(C2D, usize)> {
+        let (x, y, dir) = self.net.wrap(coord.0, coord.1, dir);
+        Some((C2D(x, y), dir))
     }
-    (bt, dir)
 }
 
 /// Move starting from coord in direction dir, for count steps, using the given
-/// step strategy. The step strategy is used to figure out what happens when we
-/// leave one side of the map.
+/// wrap strategy to figure out what happens when we leave the map. If `trace`
+/// is given, every cell we step into is recorded along with our facing.
 /// Return the new location and the new direction.
-fn do_move(map: &Map, coord: C2D, dir: usize, count: usize, strat: StepStrategy) -> (C2D, usize) {
+fn do_move(
+    map: &Map, coord: C2D, dir: usize, count: usize, strat: &dyn WrapStrategy,
+    mut trace: Option<&mut Vec<(C2D, usize)>>,
+) -> (C2D, usize) {
     let mut dir = dir;
     let mut cur = coord;
     for _ in 0..count {
         let mut next = cur + FACINGS[dir];
         let mut next_dir = dir;
-        if !map.in_bounds(&next) || map.get(&next) == Cell::Offmap {
-            (next, next_dir) = match strat {
-                StepStrategy::Flat => step_flat(map, cur, dir),
-                StepStrategy::Cube => step_cube(map, cur, dir),
-            };
+        if !map.on_map(&next) {
+            match strat.wrap(map, cur, dir) {
+                Some(wrapped) => (next, next_dir) = wrapped,
+                None => break,
+            }
         }
         if map.get(&next) == Cell::Open {
             cur = next;
             dir = next_dir;
+            if let Some(t) = trace.as_deref_mut() {
+                t.push((cur, dir));
+            }
         } else {
             break;
         }
@@ -395,9 +260,18 @@ enum Instruction {
     Move(usize),
 }
 
-fn do_path(map: &Map, instrs: &Vec<Instruction>, strat: StepStrategy, verbose: bool) {
+/// Follow the instructions using the given wrap strategy, and print the final
+/// position and password. If `trace` is given, it records the path taken: every
+/// cell visited, and every turn, along with the facing at that point.
+fn do_path(
+    map: &Map, instrs: &Vec<Instruction>, strat: &dyn WrapStrategy, verbose: bool,
+    mut trace: Option<&mut Vec<(C2D, usize)>>,
+) {
     let mut facing: usize = 0;
     let mut coord = map.start;
+    if let Some(t) = trace.as_deref_mut() {
+        t.push((coord, facing));
+    }
     if verbose {
         println!("Start:");
         map.print(&coord);
@@ -415,10 +289,14 @@ fn do_path(map: &Map, instrs: &Vec<Instruction>, strat: StepStrategy, verbose: b
             },
             Move(amt) => {
                 if verbose { println!("Moving {}...", amt) }
-                (coord, facing) = do_move(map, coord, facing, *amt, strat);
+                (coord, facing) = do_move(map, coord, facing, *amt, strat, trace.as_deref_mut());
                 if verbose {map.print(&coord)}
+                continue;
             }
         }
+        if let Some(t) = trace.as_deref_mut() {
+            t.push((coord, facing));
+        }
     }
     println!("Final row={}, column={}, facing={}", coord.1, coord.0, facing);
     println!("Password: {}", (coord.1 + 1) * 1000 + (coord.0 + 1) * 4 + facing as isize);
@@ -445,6 +323,23 @@ fn read_input(filename: &str) -> MyResult<(Map, Vec<Instruction>)> {
     Ok((mapval, instrs))
 }
 
+/// Look up a wrap strategy by name. The cube is only folded when asked for,
+/// since not every map is a cube net.
+fn strategy(name: &str, map: &Map) -> MyResult<Box<dyn WrapStrategy>> {
+    match name {
+        "flat" => Ok(Box::new(Flat)),
+        "torus" => Ok(Box::new(Torus)),
+        "cube" => Ok(Box::new(Cube::new(map)?)),
+        other => Err(format!("Unknown wrap strategy: {}", other).into()),
+    }
+}
+
+/// Usage: day22 [FILENAME [flat|torus|cube] [--trace]]
+///
+/// By default, part 1 walks the flat map and part 2 walks the cube. Given a
+/// filename, the cube walk is printed verbosely. Naming a strategy runs just
+/// that one, verbosely; with --trace, the path is drawn over the map at the
+/// end instead.
 fn main() {
     let mut filename = "input.txt";
     let mut verbose = false;
@@ -454,6 +349,24 @@ fn main() {
         verbose = true;
     }
     let (map, instrs) = read_input(filename).unwrap();
-    do_path(&map, &instrs, StepStrategy::Flat, false);
-    do_path(&map, &instrs, StepStrategy::Cube, verbose);
+    let tracing = args.iter().skip(2).any(|a| a == "--trace");
+    let named = args.iter().skip(2).find(|a| *a != "--trace");
+    let runs: Vec<(&str, bool)> = match named {
+        Some(name) => vec![(name.as_str(), !tracing)],
+        None => vec![("flat", false), ("cube", verbose && !tracing)],
+    };
+    for (name, verbose) in runs {
+        let strat = match strategy(name, &map) {
+            Ok(strat) => strat,
+            Err(e) => {
+                println!("Can't walk the map with {} wrapping: {}", name, e);
+                continue;
+            }
+        };
+        let mut trace = Vec::new();
+        do_path(&map, &instrs, strat.as_ref(), verbose, tracing.then_some(&mut trace));
+        if tracing {
+            print!("{}", map.render_trace(&trace));
+        }
+    }
 }
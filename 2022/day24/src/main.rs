@@ -7,6 +7,9 @@ use std::iter;
 use std::ops::{Add, Sub};
 use std::result::Result;
 
+use aoclib::cycle::{self, CycleInfo};
+use aoclib::search;
+
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
 #[derive(Clone, Copy, Hash, Debug, PartialEq, Eq)]
@@ -167,7 +170,7 @@ impl Map {
     }
 }
 
-fn do_search(states: &Vec<Map>, start_time: usize, reverse: bool, verbose: bool) -> usize {
+fn do_search(states: &Vec<Map>, cycle: CycleInfo, start_time: usize, reverse: bool, verbose: bool) -> usize {
     let mut start = C2D(-1, states[0].start_col as isize);
     let mut end = C2D(states[0].height as isize, states[0].end_col as isize);
     let mut initdir = CDOWN;
@@ -178,54 +181,50 @@ fn do_search(states: &Vec<Map>, start_time: usize, reverse: bool, verbose: bool)
         initdir = CUP;
     }
 
-    let mut cur: HashSet<C2D> = HashSet::new();
-    cur.insert(start);
-
-    let mut time = start_time;
-    loop {
-        let mut next: HashSet<C2D> = HashSet::new();
-        time += 1;
-        if verbose {
-            println!("Time {}", time);
-        }
-
-        let next_map = &states[time % states.len()];
-
-        for coord in cur {
+    /*
+     * Search nodes are a position paired with the blizzard state index. Since
+     * the blizzard is periodic, this keeps the state space finite.
+     */
+    let res = search::bfs(
+        [(start, cycle.reduce(start_time))],
+        |&(coord, time)| {
+            let time = cycle.reduce(time + 1);
+            let next_map = &states[time];
+            let mut next = Vec::new();
             if coord == start {
                 // Oops, starting position, can only move in initial dir
-                next.insert(coord);
+                next.push((coord, time));
                 let initstep = coord + initdir;
                 if next_map.get(&initstep) == 0 {
-                    next.insert(coord + initdir);
+                    next.push((initstep, time));
                 }
             } else {
                 // Ok, try to move any direction
                 if next_map.get(&coord) == 0 {
-                    next.insert(coord); // we can only wait if no blizzard
+                    next.push((coord, time)); // we can only wait if no blizzard
                 }
                 for dir in COORD_DIRECTIONS {
                     let new_coord = coord + *dir;
-                    if new_coord == end {
-                        return time; // WE WIN
-                    }
-                    if !next_map.in_bounds(&new_coord) {
-                        continue;
-                    }
-                    if next_map.get(&new_coord) == 0 {
-                        next.insert(new_coord);
+                    let clear = next_map.in_bounds(&new_coord) && next_map.get(&new_coord) == 0;
+                    if new_coord == end || clear {
+                        next.push((new_coord, time));
                     }
                 }
             }
-        }
+            next
+        },
+        |&(coord, _)| coord == end,
+    );
 
-        if verbose {
-            next_map.print(&next);
+    if verbose {
+        for (i, (coord, time)) in res.target_path().unwrap().iter().enumerate() {
+            println!("Time {}", start_time + i);
+            states[*time].print(&HashSet::from([*coord]));
             println!();
         }
-
-        cur = next;
     }
+
+    start_time + res.target_distance().expect("no path through the blizzard")
 }
 
 fn main() {
@@ -242,14 +241,11 @@ fn main() {
      * there could be a smaller period if we got lucky. Precompute the blizzard
      * states so that we don't need to worry about it later on.
      */
-    let mut steps = vec![Map::read(filename).unwrap()];
-    loop {
-        let next = steps[steps.len() - 1].step_blizzard();
-        if next.arr == steps[0].arr {
-            break;
-        }
-        steps.push(next);
-    }
+    let (cycle, steps) = cycle::find_cycle(
+        Map::read(filename).unwrap(),
+        |map| map.step_blizzard(),
+        |map| map.arr.clone(),
+    );
 
     /*
      * Now that we have the blizzard states, we need to do a search. The best
@@ -257,10 +253,10 @@ fn main() {
      * possible paths at once, in lockstep, synchronized by the current time.
      * The first one which can come up with the solution is the winner.
      */
-    let time = do_search(&steps, 0, false, verbose);
+    let time = do_search(&steps, cycle, 0, false, verbose);
     println!("Minimum time: {}", time);
-    let back = do_search(&steps, time, true, verbose);
+    let back = do_search(&steps, cycle, time, true, verbose);
     println!("Time to go back: {}", back);
-    let out = do_search(&steps, back, false, verbose);
+    let out = do_search(&steps, cycle, back, false, verbose);
     println!("Time to get out again: {}", out);
 }
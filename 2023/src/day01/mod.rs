@@ -69,5 +69,5 @@ pub fn run(fln: &str) -> RunResult {
     let er = Regex::new("[0-9]|enin|thgie|neves|xis|evif|ruof|eerht|owt|eno").unwrap();
     let part2 = get_line_sum(fln, &|l| get_spelled_line_number(&re, &er, l)).unwrap();
     println!("Part 2: {}", part2);
-    return_part1and2(part1 as isize, part2 as isize)
+    return_part1and2(part1, part2)
 }
@@ -73,5 +73,5 @@ pub fn run(fln: &str) -> RunResult {
     println!("Part 1: {}", possible);
     let powers = sum_games(fln, &power_minimum)?;
     println!("Part 2: {}", powers);
-    return_part1and2(possible as isize, powers as isize)
+    return_part1and2(possible, powers)
 }
@@ -62,5 +62,5 @@ pub fn run(fln: &str) -> RunResult {
     }
     println!("Part 2: {}", sum_ratios);
 
-    return_part1and2(part_number_sum as isize, sum_ratios as isize)
+    return_part1and2(part_number_sum, sum_ratios)
 }
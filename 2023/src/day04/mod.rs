@@ -41,5 +41,5 @@ pub fn run(fln: &str) -> RunResult {
     }
     println!("Part 2: {}", total);
 
-    return_part1and2(score as isize, total as isize)
+    return_part1and2(score, total)
 }
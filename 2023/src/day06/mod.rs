@@ -1,63 +1,36 @@
 use std::fs::File;
 use std::io::Read;
 
+use aoclib::checked::Num;
+use aoclib::math;
+
 use crate::util::MyResult;
 use crate::util::read_ints;
 use crate::util::return_part1and2;
 use crate::util::RunResult;
 
-fn combine_numbers(nums: &Vec<usize>) -> MyResult<usize> {
+/// Part 2 reads each line as one number, ignoring the spaces.
+fn combine_numbers(nums: &[usize]) -> MyResult<usize> {
     let mut combined: usize = 0;
     for num in nums {
-        let mut found = false;
-        for pow in 1..10 {
-            if *num < usize::pow(10, pow) {
-                combined = usize::pow(10, pow) * combined + *num;
-                found = true;
-                break
-            }
-        }
-        if !found {
-            return Err("numbers too large to combine".into());
-        }
+        let digits = num.checked_ilog10().unwrap_or(0) + 1;
+        combined = combined
+            .checked_mul(10usize.pow(digits))
+            .and_then(|c| c.checked_add(*num))
+            .ok_or("numbers too large to combine")?;
     }
     Ok(combined)
 }
 
-fn ways_to_win_bsearch(time: usize, record: usize) -> usize {
-    let best = time / 2;
-
-    let mut upper = best;
-    let mut lower = 0;
-
-    while lower < upper {
-        let mid = (upper + lower) / 2;
-        let score = mid * (time - mid);
-        if score > record {
-            upper = mid - 1;
-        } else {
-            lower = mid + 1;
-        }
-    }
-    // The loop is over: lower == upper.
-    let score = upper * (time - upper);
-    let lower_bound = if score > record {upper} else {upper + 1};
-
-    upper = time;
-    lower = best;
-    while lower < upper {
-        let mid = (upper + lower) / 2;
-        let score = mid * (time - mid);
-        if score > record {
-            lower = mid + 1;
-        } else {
-            upper = mid - 1;
-        }
-    }
-    let score = upper * (time - upper);
-    let upper_bound = if score > record {upper} else {upper - 1};
-
-    upper_bound - lower_bound + 1
+/// Holding the button for h ms wins when h * (time - h) > record, that is,
+/// when h^2 - time*h + record < 0.
+fn ways_to_win(time: usize, record: usize) -> MyResult<usize> {
+    let time = i64::try_from(time)?;
+    let record = i64::try_from(record)?;
+    Ok(match math::quadratic_below(1, -time, record) {
+        Some((lo, hi)) => (hi.min(time) - lo.max(0) + 1).max(0) as usize,
+        None => 0,
+    })
 }
 
 pub fn run(fln: &str) -> RunResult {
@@ -77,16 +50,16 @@ pub fn run(fln: &str) -> RunResult {
 
     assert!(times.len() == dists.len());
 
-    let mut product = 1;
+    let mut product = Num(1usize);
     for i in 0..times.len() {
-        product *= ways_to_win_bsearch(times[i] as usize, dists[i] as usize);
+        product *= Num(ways_to_win(times[i], dists[i])?);
     }
     println!("Part 1: {}", product);
 
     let time = combine_numbers(&times)?;
     let dist = combine_numbers(&dists)?;
-    let part2 = ways_to_win_bsearch(time, dist);
-    println!("Part 2: {}", ways_to_win_bsearch(time, dist));
+    let part2 = ways_to_win(time, dist)?;
+    println!("Part 2: {}", part2);
 
-    return_part1and2(product as isize, part2 as isize)
+    return_part1and2(product.0, part2)
 }
@@ -7,6 +7,7 @@ use crate::util::MyResult;
 use crate::util::return_part1and2;
 use crate::util::RunResult;
 
+use aoclib::checked::{self, Num, Overflow};
 use regex::Regex;
 
 // Using an enum would be more "rust-y" but god that's a ton of boilerplate
@@ -131,12 +132,13 @@ impl Hand {
     }
 }
 
-fn score(hands: &Vec<Hand>) -> isize {
-    let mut value: isize = 0;
+fn score(hands: &Vec<Hand>) -> Result<isize, Overflow> {
+    let mut value = Num(0isize);
     for (i, hand) in hands.iter().enumerate() {
-        value += (i as isize + 1) * hand.bid;
+        let rank: isize = checked::cast(i + 1)?;
+        value += Num(rank) * Num(hand.bid);
     }
-    value
+    Ok(value.0)
 }
 
 pub fn run(fln: &str) -> RunResult {
@@ -148,7 +150,7 @@ pub fn run(fln: &str) -> RunResult {
         hands.push(Hand::new(&line, &expr)?);
     }
     hands.sort();
-    let part1 = score(&hands);
+    let part1 = score(&hands)?;
     println!("Part 1: {}", part1);
 
     for hand in hands.iter_mut() {
@@ -156,7 +158,7 @@ pub fn run(fln: &str) -> RunResult {
         hand.kind = hand.compute_kind();
     }
     hands.sort();
-    let part2 = score(&hands);
+    let part2 = score(&hands)?;
     println!("Part 2: {}", part2);
     return_part1and2(part1, part2)
 }
@@ -1,12 +1,14 @@
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 
+use aoclib::cycle;
+use aoclib::intern::Interner;
+use aoclib::math::{self, CrtError};
 use regex::Regex;
 
-use crate::util::return_part1;
-use crate::util::RunResult;
+use crate::util::return_part1and2;
+use crate::util::{MyResult, RunResult};
 
 enum Dir {
     Left,
@@ -24,6 +26,115 @@ impl Dir {
     }
 }
 
+/// A move through the network: the node we're on, and how far through the
+/// instructions we are.
+type Position = (u32, usize);
+
+fn step(net: &[(u32, u32)], instrs: &[Dir], (node, i): Position) -> Position {
+    let next = match instrs[i] {
+        Dir::Left => net[node as usize].0,
+        Dir::Right => net[node as usize].1,
+    };
+    (next, (i + 1) % instrs.len())
+}
+
+/// The steps at which one ghost is on a node ending in Z. Once the ghost gets
+/// back to a position it's been in before, its walk repeats forever: so there
+/// are some one-off hits before the cycle starts, and then the hits within
+/// the cycle recur every `period` steps.
+struct GhostCycle {
+    offset: i64,
+    period: i64,
+    transient: Vec<i64>,
+    hits: Vec<i64>,
+}
+
+fn analyze_ghost(net: &[(u32, u32)], instrs: &[Dir], start: u32, at_end: &[bool]) -> GhostCycle {
+    let (cycle, history) = cycle::find_cycle((start, 0), |pos| step(net, instrs, *pos), |pos| *pos);
+    let (transient, hits) = history
+        .iter()
+        .enumerate()
+        .filter(|(_, (node, _))| at_end[*node as usize])
+        .map(|(t, _)| t as i64)
+        .partition(|t| *t < cycle.start as i64);
+    GhostCycle{offset: cycle.start as i64, period: cycle.len as i64, transient, hits}
+}
+
+/// A set of step counts: some one-off times, plus every time from `from`
+/// onward which matches one of the (residue, modulus) progressions.
+struct Times {
+    once: Vec<i64>,
+    from: i64,
+    progressions: Vec<(i64, i64)>,
+}
+
+impl Times {
+    fn from_ghost(g: GhostCycle) -> Times {
+        let progressions = g.hits.iter().map(|h| (h % g.period, g.period)).collect();
+        Times{once: g.transient, from: g.offset, progressions}
+    }
+
+    fn contains(&self, t: i64) -> bool {
+        self.once.contains(&t)
+            || (t >= self.from && self.progressions.iter().any(|(r, m)| t.rem_euclid(*m) == *r))
+    }
+
+    /// The times in both sets. Progressions are intersected with the CRT, and
+    /// pairs which never line up are dropped.
+    fn intersect(&self, other: &Times) -> Result<Times, CrtError> {
+        let mut once: Vec<i64> = self.once.iter().copied().filter(|t| other.contains(*t)).collect();
+        once.extend(other.once.iter().copied().filter(|t| self.contains(*t)));
+        once.sort();
+        once.dedup();
+        let mut progressions = Vec::new();
+        for a in self.progressions.iter() {
+            for b in other.progressions.iter() {
+                match math::crt([*a, *b]) {
+                    Ok(p) => progressions.push(p),
+                    Err(CrtError::Inconsistent{..}) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(Times{once, from: self.from.max(other.from), progressions})
+    }
+
+    fn first(&self) -> Option<i64> {
+        let repeating = self.progressions.iter().map(|(r, m)| {
+            if *r >= self.from { *r } else { r + (self.from - r + m - 1) / m * m }
+        });
+        self.once.iter().copied().chain(repeating).min()
+    }
+}
+
+/*
+ * Every ghost starting on a node ending in A has to be on a node ending in Z
+ * at the same time. The inputs are built so that each ghost hits exactly one
+ * Z node, at a multiple of its cycle length, making the answer a simple lcm,
+ * but we don't rely on that: work out each ghost's actual hit times and
+ * intersect them.
+ */
+fn all_ghosts(net: &[(u32, u32)], instrs: &[Dir], names: &Interner) -> MyResult<i64> {
+    let at_end: Vec<bool> = names.names().map(|n| n.ends_with('Z')).collect();
+    let mut times: Option<Times> = None;
+    for (id, name) in names.names().enumerate() {
+        if !name.ends_with('A') {
+            continue;
+        }
+        let ghost = analyze_ghost(net, instrs, id as u32, &at_end);
+        if ghost.transient.is_empty() && ghost.hits.is_empty() {
+            return Err(format!("ghost starting at {} never reaches a node ending in Z", name).into());
+        }
+        let ghost = Times::from_ghost(ghost);
+        times = Some(match times {
+            None => ghost,
+            Some(t) => t.intersect(&ghost)?,
+        });
+    }
+    let times = times.ok_or("no nodes ending in A")?;
+    Ok(times.first().ok_or("the ghosts are never all on nodes ending in Z at once")?)
+}
+
 pub fn run(fln: &str) -> RunResult {
     let mut l = BufReader::new(File::open(fln)?).lines();
 
@@ -33,29 +144,34 @@ pub fn run(fln: &str) -> RunResult {
     l.next().ok_or("not enough input")??;
 
     let expr = Regex::new(r"(\w{3}) = \((\w{3}), (\w{3})\)")?;
-    let mut net: HashMap<String, (String, String)> = HashMap::new();
+    let mut names = Interner::new();
+    let mut net: Vec<Option<(u32, u32)>> = Vec::new();
 
     for line in l {
         let line = line?;
         let m = expr.captures(&line).ok_or("invalid network node")?;
-        let src = String::from(m.get(1).unwrap().as_str());
-        let left = String::from(m.get(2).unwrap().as_str());
-        let right = String::from(m.get(3).unwrap().as_str());
-        net.insert(src, (left, right));
+        let src = names.intern(m.get(1).unwrap().as_str());
+        let left = names.intern(m.get(2).unwrap().as_str());
+        let right = names.intern(m.get(3).unwrap().as_str());
+        net.resize(names.len(), None);
+        net[src as usize] = Some((left, right));
     }
+    let net = net
+        .into_iter()
+        .enumerate()
+        .map(|(id, n)| n.ok_or_else(|| format!("missing node {}", names.name(id as u32))))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let mut curr = "AAA";
+    let mut pos = (names.get("AAA").ok_or("missing node AAA")?, 0);
+    let end = names.get("ZZZ").ok_or("missing node ZZZ")?;
     let mut steps = 0;
-    while curr != "ZZZ" {
-        let dir = &instrs[steps % instrs.len()];
-        let next = net.get(curr).ok_or("missing node")?;
+    while pos.0 != end {
+        pos = step(&net, &instrs, pos);
         steps += 1;
-        match dir {
-            Dir::Left => {curr = &next.0},
-            Dir::Right => {curr = &next.1},
-        }
     }
-
     println!("Part 1: {}", steps);
-    return_part1(steps as isize)
+
+    let all = all_ghosts(&net, &instrs, &names)?;
+    println!("Part 2: {}", all);
+    return_part1and2(steps, all)
 }
@@ -1,7 +1,10 @@
 use std::env;
+use std::panic;
 use std::iter::Iterator;
 use std::time::Instant;
 
+use aoclib::bigint::BigInt;
+use aoclib::checked;
 use util::RunResult;
 
 mod arr;
@@ -17,7 +20,9 @@ mod day07;
 mod day08;
 
 type Runner = fn(&str) -> RunResult;
-struct TestCase(Runner, Option<isize>, Option<isize>, Option<isize>, Option<isize>);
+/// Expected answers are i128 so the tables can be constants; the answers
+/// themselves are BigInt.
+struct TestCase(Runner, Option<i128>, Option<i128>, Option<i128>, Option<i128>);
 
 const DAYS: &[TestCase] = &[
     /* Part 1 uses two different examples, easiest to stick to example 1 */
@@ -28,12 +33,28 @@ const DAYS: &[TestCase] = &[
     TestCase(day05::run, Some(35), Some(46), Some(621354867), Some(15880236)),
     TestCase(day06::run, Some(288), Some(71503), Some(3316275), Some(27102791)),
     TestCase(day07::run, Some(6440), Some(5905), Some(250957639), Some(251515496)),
-    TestCase(day08::run, Some(2), None, Some(17621), None),
+    TestCase(day08::run, Some(2), Some(2), Some(17621), Some(20685524831999)),
 ];
 
-fn run_one(case: &TestCase, fln: &str, expected: Option<(Option<isize>, Option<isize>)>) -> bool {
+/// Run a solution, and in checked mode (see aoclib::checked) turn an overflow
+/// panic into an error naming the day, so the other days still get to run.
+fn run_checked(dayno: u32, runner: Runner, fln: &str) -> RunResult {
+    if !checked::is_strict() {
+        return runner(fln);
+    }
+    panic::catch_unwind(|| runner(fln)).unwrap_or_else(|e| {
+        let msg = e
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| e.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown panic");
+        Err(format!("day {}: {}", dayno, msg).into())
+    })
+}
+
+fn run_one(dayno: u32, case: &TestCase, fln: &str, expected: Option<(Option<i128>, Option<i128>)>) -> bool {
     let start = Instant::now();
-    let res = case.0(fln);
+    let res = run_checked(dayno, case.0, fln);
     let elapsed = start.elapsed();
     match res {
         Err(e) => {
@@ -42,7 +63,7 @@ fn run_one(case: &TestCase, fln: &str, expected: Option<(Option<isize>, Option<i
         }
         Ok((p1, p2)) => {
             if let Some((e1, e2)) = expected {
-                if p1 == e1 && p2 == e2 {
+                if p1 == e1.map(BigInt::from) && p2 == e2.map(BigInt::from) {
                     println!("✅ in {}.{:03}s", elapsed.as_secs(), elapsed.subsec_millis());
                     true
                 } else {
@@ -61,9 +82,9 @@ fn do_run(dayno: u32, case: &TestCase, example: &str, fln: &str) -> usize {
     println!("==> Day {} <==", dayno);
     let mut fails = 0;
     println!(" -> Example:");
-    if !run_one(case, example, Some((case.1, case.2))) { fails += 1 }
+    if !run_one(dayno, case, example, Some((case.1, case.2))) { fails += 1 }
     println!(" -> Puzzle:");
-    if !run_one(case, fln, Some((case.3, case.4))) { fails += 1 }
+    if !run_one(dayno, case, fln, Some((case.3, case.4))) { fails += 1 }
     println!();
     fails
 }
@@ -93,8 +114,16 @@ fn run_all() -> usize {
     fails
 }
 
+/// Usage: [all | DAY [FILENAME]] [--checked]
+///
+/// With --checked, arithmetic through aoclib::checked::Num is checked for
+/// overflow, even in release builds.
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    if let Some(pos) = args.iter().position(|a| a == "--checked") {
+        args.remove(pos);
+        checked::set_strict(true);
+    }
     if args.len() < 2 || args[1] == "all" {
         run_all();
     } else {
@@ -102,9 +131,9 @@ fn main() {
         let case = &DAYS[day as usize - 1];
 
         if args.len() >= 3 {
-            run_one(case, &args[2], None);
+            run_one(day, case, &args[2], None);
         } else {
-            run_one(case, &default_input(day), Some((case.3, case.4)));
+            run_one(day, case, &default_input(day), Some((case.3, case.4)));
         }
     }
 }
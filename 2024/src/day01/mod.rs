@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use crate::util::MyResult;
+use crate::util::return_part1and2;
 use crate::util::RunResult;
 use crate::util::read_arr;
 
@@ -44,5 +45,5 @@ fn compute_similarity(a: &Vec<usize>, b: &Vec<usize>) -> usize {
 
 pub fn run(fln: &str) -> RunResult {
     let (a, b) = read_lists(fln)?;
-    Ok((Some(compute_distance(&a, &b) as isize), Some(compute_similarity(&a, &b) as isize)))
+    return_part1and2(compute_distance(&a, &b), compute_similarity(&a, &b))
 }
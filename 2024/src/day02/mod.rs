@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use crate::util::read_ints;
+use crate::util::return_part1and2;
 use crate::util::RunResult;
 
 fn is_safe(report: &Vec<i32>) -> bool {
@@ -40,7 +41,7 @@ pub fn run(fln: &str) -> RunResult {
     for line in reader.lines() {
         reports.push(read_ints(&line?)?);
     }
-    let num_safe = reports.iter().map(|r| if is_safe(r) { 1 } else { 0 }).sum();
-    let num_dampener = reports.iter().map(|r| if is_safe_with_dampener(r) { 1 } else { 0 }).sum();
-    Ok((Some(num_safe), Some(num_dampener)))
+    let num_safe: usize = reports.iter().map(|r| if is_safe(r) { 1 } else { 0 }).sum();
+    let num_dampener: usize = reports.iter().map(|r| if is_safe_with_dampener(r) { 1 } else { 0 }).sum();
+    return_part1and2(num_safe, num_dampener)
 }
@@ -1,13 +1,20 @@
 use std::{error::Error, num::ParseIntError};
 use std::str::FromStr;
 
+use aoclib::bigint::BigInt;
+
 pub type MyResult<T> = Result<T, Box<dyn Error>>;
-pub type RunResult = MyResult<(Option<isize>, Option<isize>)>;
+pub type RunResult = MyResult<(Option<BigInt>, Option<BigInt>)>;
 
+/// Answers are reported as BigInt, so any integer answer fits exactly.
 #[allow(dead_code)]
-pub fn return_part1(v: isize) -> RunResult { Ok((Some(v), None)) }
-#[allow(dead_code)]
-pub fn return_part1and2(v: isize, w: isize) -> RunResult { Ok((Some(v), Some(w))) }
+pub fn return_part1<T: Into<BigInt>>(v: T) -> RunResult {
+    Ok((Some(v.into()), None))
+}
+
+pub fn return_part1and2<T: Into<BigInt>, U: Into<BigInt>>(v: T, w: U) -> RunResult {
+    Ok((Some(v.into()), Some(w.into())))
+}
 
 #[allow(dead_code)]
 pub fn read_ints<T: FromStr>(s: &str) -> Result<Vec<T>, ParseIntError>
@@ -0,0 +1,665 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Rem, Sub, SubAssign};
+use std::str::FromStr;
+
+/// An arbitrary-precision signed integer. The magnitude is stored as base 2^32
+/// limbs, least significant first, with no trailing zero limbs. Zero is always
+/// non-negative, so derived equality and hashing are correct.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    neg: bool,
+    mag: Vec<u32>,
+}
+
+/* Magnitude helpers. All inputs and outputs are trimmed. */
+
+fn trim(mut v: Vec<u32>) -> Vec<u32> {
+    while v.last() == Some(&0) {
+        v.pop();
+    }
+    v
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u64;
+    for (i, x) in a.iter().enumerate() {
+        let sum = *x as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        out.push(carry as u32);
+    }
+    out
+}
+
+/// Requires a >= b.
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, x) in a.iter().enumerate() {
+        let mut diff = *x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        }
+        out.push(diff as u32);
+    }
+    trim(out)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let cur = out[i + j] as u64 + *x as u64 * *y as u64 + carry;
+            out[i + j] = cur as u32;
+            carry = cur >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    trim(out)
+}
+
+fn divmod_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut out = vec![0u32; a.len()];
+    let mut rem = 0u64;
+    for (i, x) in a.iter().enumerate().rev() {
+        let cur = (rem << 32) | *x as u64;
+        out[i] = (cur / d as u64) as u32;
+        rem = cur % d as u64;
+    }
+    (trim(out), rem as u32)
+}
+
+fn shl1_or(v: &mut Vec<u32>, bit: u32) {
+    let mut carry = bit;
+    for x in v.iter_mut() {
+        let next = *x >> 31;
+        *x = (*x << 1) | carry;
+        carry = next;
+    }
+    if carry > 0 {
+        v.push(carry);
+    }
+}
+
+/// Binary long division. Slow-ish, but puzzle numbers are not that big.
+fn divmod_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if b.is_empty() {
+        panic!("attempt to divide by zero");
+    }
+    if cmp_mag(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let (q, r) = divmod_small(a, b[0]);
+        return (q, trim(vec![r]));
+    }
+    let mut q = vec![0u32; a.len()];
+    let mut r: Vec<u32> = Vec::new();
+    for i in (0..a.len() * 32).rev() {
+        shl1_or(&mut r, (a[i / 32] >> (i % 32)) & 1);
+        if cmp_mag(&r, b) != Ordering::Less {
+            r = sub_mag(&r, b);
+            q[i / 32] |= 1 << (i % 32);
+        }
+    }
+    (trim(q), r)
+}
+
+impl BigInt {
+    fn from_parts(neg: bool, mag: Vec<u32>) -> Self {
+        let mag = trim(mag);
+        BigInt{neg: neg && !mag.is_empty(), mag}
+    }
+
+    pub fn zero() -> Self {
+        BigInt::default()
+    }
+
+    pub fn one() -> Self {
+        BigInt::from(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.neg
+    }
+
+    pub fn is_positive(&self) -> bool {
+        !self.neg && !self.mag.is_empty()
+    }
+
+    pub fn signum(&self) -> isize {
+        if self.neg { -1 } else if self.mag.is_empty() { 0 } else { 1 }
+    }
+
+    pub fn abs(&self) -> Self {
+        BigInt{neg: false, mag: self.mag.clone()}
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.mag.first().is_none_or(|x| x & 1 == 0)
+    }
+
+    /// Truncating division, returning the quotient and remainder like `/` and
+    /// `%` do for primitive integers.
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        let (q, r) = divmod_mag(&self.mag, &rhs.mag);
+        (BigInt::from_parts(self.neg != rhs.neg, q), BigInt::from_parts(self.neg, r))
+    }
+
+    /// Greatest common divisor, always non-negative.
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.abs();
+        let mut b = other.abs();
+        while !b.is_zero() {
+            let r = &a % &b;
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut base = self.clone();
+        let mut res = BigInt::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                res = &res * &base;
+            }
+            base = &base * &base;
+            exp >>= 1;
+        }
+        res
+    }
+
+    /// Floor of the square root, or None for negative numbers.
+    pub fn isqrt(&self) -> Option<Self> {
+        if self.neg {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(BigInt::zero());
+        }
+        /* Newton's method, starting from a power of two above the root. */
+        let bits = self.mag.len() * 32 - self.mag.last().unwrap().leading_zeros() as usize;
+        let mut x = BigInt::one().shl(bits.div_ceil(2));
+        loop {
+            let y = (&x + &(self / &x)).shr(1);
+            if y >= x {
+                return Some(x);
+            }
+            x = y;
+        }
+    }
+
+    fn shl(&self, bits: usize) -> Self {
+        let mut mag = vec![0u32; bits / 32];
+        let shift = bits % 32;
+        let mut carry = 0u32;
+        for x in self.mag.iter() {
+            if shift == 0 {
+                mag.push(*x);
+            } else {
+                mag.push((*x << shift) | carry);
+                carry = *x >> (32 - shift);
+            }
+        }
+        mag.push(carry);
+        BigInt::from_parts(self.neg, mag)
+    }
+
+    fn shr(&self, bits: usize) -> Self {
+        let skip = bits / 32;
+        let shift = bits % 32;
+        if skip >= self.mag.len() {
+            return BigInt::zero();
+        }
+        let src = &self.mag[skip..];
+        let mut mag = Vec::with_capacity(src.len());
+        for (i, x) in src.iter().enumerate() {
+            let hi = if shift == 0 { 0 } else { src.get(i + 1).map_or(0, |h| h << (32 - shift)) };
+            mag.push((x >> shift) | hi);
+        }
+        BigInt::from_parts(self.neg, mag)
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(v: i128) -> Self {
+        let mut m = v.unsigned_abs();
+        let mut mag = Vec::new();
+        while m > 0 {
+            mag.push(m as u32);
+            m >>= 32;
+        }
+        BigInt::from_parts(v < 0, mag)
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(mut m: u128) -> Self {
+        let mut mag = Vec::new();
+        while m > 0 {
+            mag.push(m as u32);
+            m >>= 32;
+        }
+        BigInt::from_parts(false, mag)
+    }
+}
+
+macro_rules! from_prim {
+    ($($t:ty => $via:ty),*) => {$(
+        impl From<$t> for BigInt {
+            fn from(v: $t) -> Self {
+                BigInt::from(v as $via)
+            }
+        }
+    )*};
+}
+from_prim!(i8 => i128, i16 => i128, i32 => i128, i64 => i128, isize => i128,
+           u8 => u128, u16 => u128, u32 => u128, u64 => u128, usize => u128);
+
+impl TryFrom<&BigInt> for i128 {
+    type Error = String;
+    fn try_from(v: &BigInt) -> Result<i128, String> {
+        if v.mag.len() > 4 {
+            return Err(format!("{} does not fit in 128 bits", v));
+        }
+        let mut m = 0u128;
+        for x in v.mag.iter().rev() {
+            m = (m << 32) | *x as u128;
+        }
+        if v.neg {
+            0i128.checked_sub_unsigned(m)
+        } else {
+            i128::try_from(m).ok()
+        }.ok_or_else(|| format!("{} does not fit in 128 bits", v))
+    }
+}
+
+macro_rules! try_into_prim {
+    ($($t:ty),*) => {$(
+        impl TryFrom<&BigInt> for $t {
+            type Error = String;
+            fn try_from(v: &BigInt) -> Result<$t, String> {
+                let wide = i128::try_from(v)?;
+                <$t>::try_from(wide).map_err(|_| format!("{} does not fit in {}", v, stringify!($t)))
+            }
+        }
+    )*};
+}
+try_into_prim!(i64, isize, u64, usize);
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.neg, other.neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.neg, self.mag.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.neg, self.mag)
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.neg == rhs.neg {
+            return BigInt::from_parts(self.neg, add_mag(&self.mag, &rhs.mag));
+        }
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => BigInt::from_parts(rhs.neg, sub_mag(&rhs.mag, &self.mag)),
+            _ => BigInt::from_parts(self.neg, sub_mag(&self.mag, &rhs.mag)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_parts(self.neg != rhs.neg, mul_mag(&self.mag, &rhs.mag))
+    }
+}
+
+impl Div for &BigInt {
+    type Output = BigInt;
+    fn div(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).0
+    }
+}
+
+impl Rem for &BigInt {
+    type Output = BigInt;
+    fn rem(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).1
+    }
+}
+
+/* Forward the owned variants of each operator to the reference version. */
+macro_rules! forward_binop {
+    ($($tr:ident $f:ident),*) => {$(
+        impl $tr<BigInt> for BigInt {
+            type Output = BigInt;
+            fn $f(self, rhs: BigInt) -> BigInt { (&self).$f(&rhs) }
+        }
+        impl $tr<&BigInt> for BigInt {
+            type Output = BigInt;
+            fn $f(self, rhs: &BigInt) -> BigInt { (&self).$f(rhs) }
+        }
+        impl $tr<BigInt> for &BigInt {
+            type Output = BigInt;
+            fn $f(self, rhs: BigInt) -> BigInt { self.$f(&rhs) }
+        }
+    )*};
+}
+forward_binop!(Add add, Sub sub, Mul mul, Div div, Rem rem);
+
+macro_rules! forward_assign {
+    ($($tr:ident $f:ident $op:ident),*) => {$(
+        impl $tr<&BigInt> for BigInt {
+            fn $f(&mut self, rhs: &BigInt) { *self = (&*self).$op(rhs); }
+        }
+        impl $tr<BigInt> for BigInt {
+            fn $f(&mut self, rhs: BigInt) { *self = (&*self).$op(&rhs); }
+        }
+    )*};
+}
+forward_assign!(AddAssign add_assign add, SubAssign sub_assign sub, MulAssign mul_assign mul);
+
+impl Sum for BigInt {
+    fn sum<I: Iterator<Item = BigInt>>(iter: I) -> Self {
+        iter.fold(BigInt::zero(), |acc, x| acc + x)
+    }
+}
+
+impl Product for BigInt {
+    fn product<I: Iterator<Item = BigInt>>(iter: I) -> Self {
+        iter.fold(BigInt::one(), |acc, x| acc * x)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad_integral(true, "", "0");
+        }
+        /* Peel off nine decimal digits at a time. */
+        let mut chunks: Vec<u32> = Vec::new();
+        let mut mag = self.mag.clone();
+        while !mag.is_empty() {
+            let (q, r) = divmod_small(&mag, 1_000_000_000);
+            chunks.push(r);
+            mag = q;
+        }
+        let mut s = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            s.push_str(&format!("{:09}", chunk));
+        }
+        f.pad_integral(!self.neg, "", &s)
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBigIntError(String);
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid integer {:?}", self.0)
+    }
+}
+
+impl Error for ParseBigIntError {}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (neg, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigIntError(s.to_string()));
+        }
+        let mut mag: Vec<u32> = Vec::new();
+        for chunk in digits.as_bytes().chunks(9) {
+            let val: u32 = std::str::from_utf8(chunk).unwrap().parse().unwrap();
+            let scale = [10u32.pow(chunk.len() as u32)];
+            mag = add_mag(&mul_mag(&mag, &scale), &trim(vec![val]));
+        }
+        Ok(BigInt::from_parts(neg, mag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xorshift(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    /* A mix of extremes, limb boundaries and random values of every width. */
+    fn operands() -> Vec<i128> {
+        let mut ops = vec![0, 1, -1, 2, -2, i128::MAX, i128::MIN, i128::MAX - 1, i128::MIN + 1];
+        for shift in [31, 32, 33, 63, 64, 65, 95, 96, 97, 126] {
+            ops.extend([1i128 << shift, (1i128 << shift) - 1, -(1i128 << shift), 1 - (1i128 << shift)]);
+        }
+        let mut seed = 0x2545f4914f6cdd1du64;
+        for _ in 0..60 {
+            let wide = ((xorshift(&mut seed) as u128) << 64 | xorshift(&mut seed) as u128) as i128;
+            ops.push(wide >> (xorshift(&mut seed) % 127));
+        }
+        ops
+    }
+
+    fn big(v: i128) -> BigInt {
+        BigInt::from(v)
+    }
+
+    #[test]
+    fn arithmetic_matches_i128() {
+        let ops = operands();
+        for &a in ops.iter() {
+            for &b in ops.iter() {
+                let (x, y) = (big(a), big(b));
+                let sum = &x + &y;
+                let diff = &x - &y;
+                let prod = &x * &y;
+                assert_eq!(&sum - &y, x, "{} + {} - {}", a, b, b);
+                assert_eq!(&diff + &y, x, "{} - {} + {}", a, b, b);
+                if let Some(s) = a.checked_add(b) {
+                    assert_eq!(sum, big(s), "{} + {}", a, b);
+                }
+                if let Some(d) = a.checked_sub(b) {
+                    assert_eq!(diff, big(d), "{} - {}", a, b);
+                }
+                if let Some(p) = a.checked_mul(b) {
+                    assert_eq!(prod, big(p), "{} * {}", a, b);
+                }
+                assert_eq!(x.cmp(&y), a.cmp(&b), "{} <=> {}", a, b);
+                if b == 0 {
+                    continue;
+                }
+                /* Products that overflow i128 must still divide back exactly. */
+                assert_eq!(prod.div_rem(&y), (x.clone(), BigInt::zero()), "{} * {} / {}", a, b, b);
+                let (q, r) = x.div_rem(&y);
+                match (a.checked_div(b), a.checked_rem(b)) {
+                    (Some(eq), Some(er)) => assert_eq!((&q, &r), (&big(eq), &big(er)), "{} / {}", a, b),
+                    _ => assert_eq!((&q, &r), (&BigInt::from(1u128 << 127), &BigInt::zero())),
+                }
+                assert_eq!(&(&q * &y) + &r, x);
+                assert!(r.is_zero() || r.is_negative() == x.is_negative(), "{} % {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn signs() {
+        for a in operands() {
+            let x = big(a);
+            assert_eq!(x.signum(), a.signum() as isize);
+            assert_eq!((x.is_negative(), x.is_positive(), x.is_zero()), (a < 0, a > 0, a == 0));
+            assert_eq!(x.is_even(), a % 2 == 0);
+            assert_eq!(-&x + x.clone(), BigInt::zero());
+            assert_eq!(x.abs(), BigInt::from(a.unsigned_abs()));
+        }
+        /* Negating zero must not produce a distinct negative zero. */
+        assert_eq!(-BigInt::zero(), BigInt::zero());
+        assert_eq!(big(5) - big(5), BigInt::zero());
+        assert_eq!(big(-3) * big(0), BigInt::zero());
+        assert_eq!(big(-3) / big(7), BigInt::zero());
+    }
+
+    #[test]
+    fn carries_across_limbs() {
+        let limb = BigInt::from(u32::MAX);
+        assert_eq!(&limb + &BigInt::one(), BigInt::from(1u64 << 32));
+        assert_eq!(&limb * &limb, BigInt::from(u32::MAX as u64 * u32::MAX as u64));
+        let top = BigInt::from(u128::MAX);
+        assert_eq!((&top + &BigInt::one()).to_string(), "340282366920938463463374607431768211456");
+        assert_eq!((&top * &top).to_string(),
+                   "115792089237316195423570985008687907852589419931798687112530834793049593217025");
+        assert_eq!(&(&top + &BigInt::one()) - &BigInt::one(), top);
+        let (q, r) = (&top * &top).div_rem(&top);
+        assert_eq!((q, r), (top.clone(), BigInt::zero()));
+        /* Long division with a multi-limb divisor and remainder. */
+        let n: BigInt = "123456789012345678901234567890123456789012345678901234567890".parse().unwrap();
+        let d: BigInt = "-98765432109876543210987".parse().unwrap();
+        let (q, r) = n.div_rem(&d);
+        assert_eq!(q.to_string(), "-1249999988609375000142391093749550070");
+        assert_eq!(r.to_string(), "29599966484956903948800");
+        assert_eq!(&(&q * &d) + &r, n);
+        assert!(!r.is_negative() && r < d.abs());
+    }
+
+    #[test]
+    fn gcd_and_pow() {
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        for _ in 0..200 {
+            let a = (xorshift(&mut seed) >> 20) as i128 - (1 << 43);
+            let b = (xorshift(&mut seed) >> 40) as i128 - (1 << 23);
+            let (mut x, mut y) = (a.abs(), b.abs());
+            while y != 0 {
+                (x, y) = (y, x % y);
+            }
+            assert_eq!(big(a).gcd(&big(b)), big(x), "gcd({}, {})", a, b);
+        }
+        assert_eq!(big(-3).pow(5), big(-243));
+        assert_eq!(big(-3).pow(0), BigInt::one());
+        assert_eq!(big(2).pow(127), BigInt::from(1u128 << 127));
+        assert_eq!(big(10).pow(40).to_string(), format!("1{}", "0".repeat(40)));
+    }
+
+    #[test]
+    fn isqrt() {
+        assert_eq!(big(-1).isqrt(), None);
+        for a in operands().into_iter().filter(|a| *a >= 0).chain(0..300) {
+            let x = big(a);
+            let r = x.isqrt().unwrap();
+            assert!(&r * &r <= x, "isqrt({}) = {}", a, r);
+            let next = &r + &BigInt::one();
+            assert!(&next * &next > x, "isqrt({}) = {}", a, r);
+        }
+        let huge = big(10).pow(60);
+        assert_eq!(huge.isqrt(), Some(big(10).pow(30)));
+        assert_eq!((&huge - &BigInt::one()).isqrt(), Some(&big(10).pow(30) - &BigInt::one()));
+    }
+
+    #[test]
+    fn conversions() {
+        for a in operands() {
+            assert_eq!(i128::try_from(&big(a)), Ok(a));
+            assert_eq!(i64::try_from(&big(a)).ok(), i64::try_from(a).ok(), "{}", a);
+            assert_eq!(u64::try_from(&big(a)).ok(), u64::try_from(a).ok(), "{}", a);
+        }
+        let over = &big(i128::MAX) + &BigInt::one();
+        assert!(i128::try_from(&over).is_err());
+        assert!(i128::try_from(&-&over).is_ok());
+        assert!(i128::try_from(&(&-&over - &BigInt::one())).is_err());
+        assert!(i128::try_from(&BigInt::from(u128::MAX)).is_err());
+        assert!(i128::try_from(&big(10).pow(50)).is_err());
+        assert_eq!(i64::try_from(&BigInt::from(i64::MIN)), Ok(i64::MIN));
+        assert!(i64::try_from(&(&BigInt::from(i64::MAX) + &BigInt::one())).is_err());
+        assert!(usize::try_from(&big(-1)).is_err());
+        assert_eq!(BigInt::from(-7i8), big(-7));
+        assert_eq!(BigInt::from(u64::MAX), big(u64::MAX as i128));
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for a in operands() {
+            let s = a.to_string();
+            assert_eq!(big(a).to_string(), s);
+            assert_eq!(s.parse::<BigInt>(), Ok(big(a)));
+        }
+        /* Chunks of nine digits must keep their leading zeros. */
+        let s = "-1000000000000000000000000000000000000000001";
+        assert_eq!(s.parse::<BigInt>().unwrap().to_string(), s);
+        assert_eq!("+42".parse::<BigInt>(), Ok(big(42)));
+        assert_eq!("-0".parse::<BigInt>(), Ok(BigInt::zero()));
+        assert_eq!("000123".parse::<BigInt>(), Ok(big(123)));
+        for bad in ["", "-", "+", "1a", " 1", "--1", "1_000"] {
+            assert_eq!(bad.parse::<BigInt>(), Err(ParseBigIntError(bad.to_string())));
+        }
+        assert_eq!(format!("{:>6}|{:<5}|{:+}", big(-42), big(7), big(3)), "   -42|7    |+3");
+        let total: BigInt = [1, 2, 3, 4].into_iter().map(big).sum();
+        let product: BigInt = [1, 2, 3, 4].into_iter().map(big).product();
+        assert_eq!((total, product), (big(10), big(24)));
+    }
+}
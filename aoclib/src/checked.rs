@@ -0,0 +1,212 @@
+use std::any::type_name;
+use std::error::Error;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Rem, Sub, SubAssign};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/*
+ * In release builds, integer overflow silently wraps, and `as` casts silently
+ * truncate, which is a great way to submit a wrong answer. Num<T> is a thin
+ * wrapper for the arithmetic where that's a risk: normally it behaves exactly
+ * like the plain integer (wrapping in release builds, panicking in debug
+ * ones), but in strict mode every operation is checked and overflow panics
+ * with a message saying what was being computed. The runners turn on strict
+ * mode with --checked, and report the panic against the day instead of
+ * crashing.
+ *
+ * So far only the 2023 solvers which multiply their way to an answer (days 6
+ * and 7) do their arithmetic in Num; the rest carry answers as BigInt to the
+ * runners, where there's nothing left to overflow. The 2022 day21 polynomial
+ * algebra that used to multiply isizes needs neither: it works in BigInt and
+ * Rational throughout. Other solvers still use plain integers internally;
+ * moving them over is a matter of wrapping the values in Num where overflow
+ * is possible, and converting with cast() instead of `as`.
+ */
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strict(on: bool) {
+    STRICT.store(on, Ordering::Relaxed);
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// An arithmetic operation or conversion whose result doesn't fit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    Op{op: &'static str, lhs: String, rhs: String, ty: &'static str},
+    Cast{value: String, from: &'static str, to: &'static str},
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Overflow::Op{op, lhs, rhs, ty} => write!(f, "overflow: {} {} {} doesn't fit in {}", lhs, op, rhs, ty),
+            Overflow::Cast{value, from, to} => write!(f, "lossy cast: {} ({}) doesn't fit in {}", value, from, to),
+        }
+    }
+}
+
+impl Error for Overflow {}
+
+/// Convert between integer types, failing instead of truncating.
+pub fn cast<U, T>(v: T) -> Result<U, Overflow>
+where T: TryInto<U> + Copy + fmt::Display {
+    v.try_into().map_err(|_| Overflow::Cast{
+        value: v.to_string(),
+        from: type_name::<T>(),
+        to: type_name::<U>(),
+    })
+}
+
+/// The primitive operations Num needs, implemented for every integer type.
+pub trait Int: Copy + PartialEq + fmt::Display
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Rem<Output = Self> {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+macro_rules! impl_int {
+    ($($t:ty)*) => {$(
+        impl Int for $t {
+            fn checked_add(self, rhs: Self) -> Option<Self> { <$t>::checked_add(self, rhs) }
+            fn checked_sub(self, rhs: Self) -> Option<Self> { <$t>::checked_sub(self, rhs) }
+            fn checked_mul(self, rhs: Self) -> Option<Self> { <$t>::checked_mul(self, rhs) }
+            fn checked_div(self, rhs: Self) -> Option<Self> { <$t>::checked_div(self, rhs) }
+            fn checked_rem(self, rhs: Self) -> Option<Self> { <$t>::checked_rem(self, rhs) }
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+        }
+    )*};
+}
+
+impl_int!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+/// An integer whose arithmetic is checked in strict mode. See above.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Num<T>(pub T);
+
+impl<T: Int> Num<T> {
+    /// Convert to another integer type, failing instead of truncating.
+    pub fn cast<U>(self) -> Result<U, Overflow>
+    where T: TryInto<U> {
+        cast(self.0)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Num<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+macro_rules! impl_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign:ident, $checked:ident, $sym:expr) => {
+        impl<T: Int> $trait for Num<T> {
+            type Output = Self;
+            fn $method(self, rhs: Self) -> Self {
+                if !is_strict() {
+                    return Num($trait::$method(self.0, rhs.0));
+                }
+                match self.0.$checked(rhs.0) {
+                    Some(v) => Num(v),
+                    None => panic!("{}", Overflow::Op{
+                        op: $sym,
+                        lhs: self.0.to_string(),
+                        rhs: rhs.0.to_string(),
+                        ty: type_name::<T>(),
+                    }),
+                }
+            }
+        }
+
+        impl<T: Int> $assign_trait for Num<T> {
+            fn $assign(&mut self, rhs: Self) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+    };
+}
+
+impl_op!(Add, add, AddAssign, add_assign, checked_add, "+");
+impl_op!(Sub, sub, SubAssign, sub_assign, checked_sub, "-");
+impl_op!(Mul, mul, MulAssign, mul_assign, checked_mul, "*");
+
+/* Division by zero panics either way, so the only overflow is MIN / -1. */
+impl<T: Int> Div for Num<T> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        if !is_strict() || rhs.0 == T::ZERO {
+            return Num(self.0 / rhs.0);
+        }
+        match self.0.checked_div(rhs.0) {
+            Some(v) => Num(v),
+            None => panic!("{}", Overflow::Op{
+                op: "/", lhs: self.0.to_string(), rhs: rhs.0.to_string(), ty: type_name::<T>(),
+            }),
+        }
+    }
+}
+
+impl<T: Int> Rem for Num<T> {
+    type Output = Self;
+    fn rem(self, rhs: Self) -> Self {
+        if !is_strict() || rhs.0 == T::ZERO {
+            return Num(self.0 % rhs.0);
+        }
+        match self.0.checked_rem(rhs.0) {
+            Some(v) => Num(v),
+            None => panic!("{}", Overflow::Op{
+                op: "%", lhs: self.0.to_string(), rhs: rhs.0.to_string(), ty: type_name::<T>(),
+            }),
+        }
+    }
+}
+
+impl<T: Int> Sum for Num<T> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Num(T::ZERO), |a, b| a + b)
+    }
+}
+
+impl<T: Int> Product for Num<T> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Num(T::ONE), |a, b| a * b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_matches_plain_integers() {
+        let (a, b) = (Num(-17i64), Num(5i64));
+        assert_eq!((a + b, a - b, a * b, a / b, a % b), (Num(-12), Num(-22), Num(-85), Num(-3), Num(-2)));
+        assert_eq!([1u8, 2, 3].map(Num).into_iter().sum::<Num<u8>>(), Num(6));
+        assert_eq!([4u8, 5, 6].map(Num).into_iter().product::<Num<u8>>(), Num(120));
+    }
+
+    /* Overflow panics in debug builds whether or not strict mode is on. */
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn debug_overflow_panics() {
+        let _ = Num(i32::MAX) + Num(1);
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(cast::<u8, i32>(200), Ok(200));
+        assert!(matches!(cast::<u8, i32>(-1), Err(Overflow::Cast{..})));
+        assert!(Num(70_000u32).cast::<u16>().is_err());
+    }
+}
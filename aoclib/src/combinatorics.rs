@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+/// Every way to choose k of the items, as references in their original
+/// order. Combinations come out in lexicographic order of position.
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    idx: Vec<usize>,
+    done: bool,
+}
+
+pub fn combinations<T>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations{items, idx: (0..k).collect(), done: k > items.len()}
+}
+
+impl<'a, T> Iterator for Combinations<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Vec<&'a T>> {
+        if self.done {
+            return None;
+        }
+        let res = self.idx.iter().map(|i| &self.items[*i]).collect();
+        /* Advance the rightmost index which still has room to move, and
+         * pack everything after it up against it. */
+        let (n, k) = (self.items.len(), self.idx.len());
+        match (0..k).rev().find(|i| self.idx[*i] < n - k + i) {
+            Some(i) => {
+                self.idx[i] += 1;
+                for j in i + 1..k {
+                    self.idx[j] = self.idx[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        Some(res)
+    }
+}
+
+/// Every ordering of the items, as references. Orderings come out in
+/// lexicographic order of position, so equal items give repeated orderings.
+pub struct Permutations<'a, T> {
+    items: &'a [T],
+    idx: Vec<usize>,
+    done: bool,
+}
+
+pub fn permutations<T>(items: &[T]) -> Permutations<'_, T> {
+    Permutations{items, idx: (0..items.len()).collect(), done: false}
+}
+
+impl<'a, T> Iterator for Permutations<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Vec<&'a T>> {
+        if self.done {
+            return None;
+        }
+        let res = self.idx.iter().map(|i| &self.items[*i]).collect();
+        /* The standard next permutation: find the last ascent, swap its left
+         * side with the smallest larger index after it, reverse the tail. */
+        match (1..self.idx.len()).rev().find(|i| self.idx[i - 1] < self.idx[*i]) {
+            Some(i) => {
+                let j = (i..self.idx.len()).rev().find(|j| self.idx[*j] > self.idx[i - 1]).unwrap();
+                self.idx.swap(i - 1, j);
+                self.idx[i..].reverse();
+            }
+            None => self.done = true,
+        }
+        Some(res)
+    }
+}
+
+/// Find k items (at distinct positions) adding up to `target`, returning
+/// their indices. Pairs are found with a hash set in one pass; larger k sorts
+/// once and fixes the smallest items in turn, finishing each with a two
+/// pointer scan, for O(n^(k-1)) overall. The sums are worked out in i128, so
+/// neither they nor the differences along the way can overflow. Panics if k
+/// is zero.
+pub fn k_sum<T>(items: &[T], k: usize, target: T) -> Option<Vec<usize>>
+where T: Copy + Into<i128> {
+    assert!(k > 0, "k_sum needs at least one item");
+    let values: Vec<i128> = items.iter().map(|v| (*v).into()).collect();
+    let target = target.into();
+    match k {
+        1 => values.iter().position(|v| *v == target).map(|i| vec![i]),
+        2 => two_sum_hashed(&values, target).map(|(i, j)| vec![i, j]),
+        _ => {
+            let mut order: Vec<usize> = (0..values.len()).collect();
+            order.sort_by_key(|i| values[*i]);
+            let sorted: Vec<i128> = order.iter().map(|i| values[*i]).collect();
+            let mut chosen = Vec::with_capacity(k);
+            sorted_k_sum(&sorted, 0, k, target, &mut chosen)
+                .then(|| chosen.iter().map(|i| order[*i]).collect())
+        }
+    }
+}
+
+fn two_sum_hashed(values: &[i128], target: i128) -> Option<(usize, usize)> {
+    let mut seen: HashMap<i128, usize> = HashMap::new();
+    for (j, v) in values.iter().enumerate() {
+        if let Some(i) = seen.get(&(target - v)) {
+            return Some((*i, j));
+        }
+        seen.entry(*v).or_insert(j);
+    }
+    None
+}
+
+/// Pick k of sorted[start..] adding to target, pushing their positions onto
+/// `chosen`. Requires k >= 2.
+fn sorted_k_sum(sorted: &[i128], start: usize, k: usize, target: i128, chosen: &mut Vec<usize>) -> bool {
+    if sorted.len() < start + k {
+        return false;
+    }
+    if k == 2 {
+        let (mut lo, mut hi) = (start, sorted.len() - 1);
+        while lo < hi {
+            let sum = sorted[lo] + sorted[hi];
+            if sum < target {
+                lo += 1;
+            } else if sum > target {
+                hi -= 1;
+            } else {
+                chosen.extend([lo, hi]);
+                return true;
+            }
+        }
+        return false;
+    }
+    for i in start..=sorted.len() - k {
+        /* Trying the same value again can't find anything new. */
+        if i > start && sorted[i] == sorted[i - 1] {
+            continue;
+        }
+        chosen.push(i);
+        if sorted_k_sum(sorted, i + 1, k - 1, target - sorted[i], chosen) {
+            return true;
+        }
+        chosen.pop();
+    }
+    false
+}
+
+/// Every submask of `mask`, from `mask` itself down to 0.
+pub struct Submasks {
+    mask: u64,
+    next: Option<u64>,
+}
+
+pub fn submasks(mask: u64) -> Submasks {
+    Submasks{mask, next: Some(mask)}
+}
+
+impl Iterator for Submasks {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let cur = self.next?;
+        self.next = (cur != 0).then(|| (cur - 1) & self.mask);
+        Some(cur)
+    }
+}
+
+/// Every n-bit mask with exactly k bits set, in increasing order.
+pub struct MasksWithBits {
+    limit: u64,
+    next: Option<u64>,
+}
+
+/// Panics if n is more than 63.
+pub fn masks_with_bits(n: u32, k: u32) -> MasksWithBits {
+    assert!(n < 64, "masks_with_bits supports at most 63 bits, got {}", n);
+    let first = (k <= n).then(|| (1u64 << k) - 1);
+    MasksWithBits{limit: 1 << n, next: first}
+}
+
+impl Iterator for MasksWithBits {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let cur = self.next?;
+        /* Gosper's hack: move the lowest movable bit up one place, and drop
+         * the bits below it back to the bottom. */
+        self.next = if cur == 0 {
+            None
+        } else {
+            let low = cur & cur.wrapping_neg();
+            let ripple = cur + low;
+            let next = (((ripple ^ cur) >> 2) / low) | ripple;
+            (next < self.limit).then_some(next)
+        };
+        Some(cur)
+    }
+}
+
+/// The positions of the set bits in `mask`, lowest first.
+pub fn bits(mut mask: u64) -> impl Iterator<Item = u32> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let bit = mask.trailing_zeros();
+        mask &= mask - 1;
+        Some(bit)
+    })
+}
+
+/// For every mask over `values.len()` slots (which must be a power of two),
+/// the index of its submask with the greatest value. Rather than walking the submasks
+/// of every mask, which is O(3^n), this passes the best answer up one bit at
+/// a time (the "sum over subsets" trick), for O(n 2^n).
+pub fn best_submasks<T: Ord>(values: &[T]) -> Vec<usize> {
+    assert!(values.len().is_power_of_two(), "best_submasks needs 2^n values, got {}", values.len());
+    let mut best: Vec<usize> = (0..values.len()).collect();
+    for bit in 0..values.len().trailing_zeros() {
+        for m in 0..best.len() {
+            if m & (1 << bit) != 0 {
+                let other = best[m ^ (1 << bit)];
+                if values[other] > values[best[m]] {
+                    best[m] = other;
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xorshift(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    /// Every vector of n indices below `below` satisfying `keep`, in
+    /// lexicographic order.
+    fn index_vectors<F: Fn(&[usize]) -> bool>(n: usize, below: usize, keep: F) -> Vec<Vec<usize>> {
+        let mut out = Vec::new();
+        for code in 0..below.pow(n as u32) {
+            let v: Vec<usize> = (0..n).rev().map(|i| code / below.pow(i as u32) % below).collect();
+            if keep(&v) {
+                out.push(v);
+            }
+        }
+        out
+    }
+
+    fn positions<T>(items: &[T], chosen: Vec<&T>) -> Vec<usize> {
+        chosen.iter().map(|r| items.iter().position(|i| std::ptr::eq(i, *r)).unwrap()).collect()
+    }
+
+    #[test]
+    fn combinations_match_brute_force() {
+        for n in 0..=6 {
+            let items: Vec<u8> = (0..n as u8).collect();
+            for k in 0..=n + 2 {
+                let got: Vec<_> = combinations(&items, k).map(|c| positions(&items, c)).collect();
+                let want = if k > n { Vec::new() } else { index_vectors(k, n, |v| v.windows(2).all(|w| w[0] < w[1])) };
+                assert_eq!(got, want, "n = {}, k = {}", n, k);
+            }
+        }
+        assert_eq!(combinations(&[1, 2, 3], 0).collect::<Vec<_>>(), vec![Vec::<&i32>::new()]);
+        assert_eq!(combinations(&[1, 2, 3], 3).count(), 1);
+        assert_eq!(combinations(&[1, 2, 3], 4).count(), 0);
+        assert_eq!(combinations(&[0; 20], 10).count(), 184756);
+    }
+
+    #[test]
+    fn permutations_match_brute_force() {
+        for n in 0..=6 {
+            let items: Vec<u8> = vec![7; n];
+            let got: Vec<_> = permutations(&items).map(|p| positions(&items, p)).collect();
+            let want = index_vectors(n, n, |v| (0..n).all(|i| v.contains(&i)));
+            assert_eq!(got, want, "n = {}", n);
+        }
+        assert_eq!(permutations(&[0; 8]).count(), 40320);
+    }
+
+    #[test]
+    fn submasks_match_brute_force() {
+        for mask in 0..1u64 << 10 {
+            let want: Vec<u64> = (0..=mask).rev().filter(|m| m & !mask == 0).collect();
+            assert_eq!(submasks(mask).collect::<Vec<_>>(), want, "mask = {:b}", mask);
+        }
+        assert_eq!(submasks(u64::MAX).take(2).collect::<Vec<_>>(), vec![u64::MAX, u64::MAX - 1]);
+    }
+
+    #[test]
+    fn best_submasks_match_brute_force() {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        for n in 0..=8 {
+            let values: Vec<u8> = (0..1 << n).map(|_| (xorshift(&mut seed) % 10) as u8).collect();
+            let best = best_submasks(&values);
+            for (mask, got) in best.iter().enumerate() {
+                let top = submasks(mask as u64).map(|m| values[m as usize]).max().unwrap();
+                assert_eq!(*got & !mask, 0, "{:b} is not a submask of {:b}", got, mask);
+                assert_eq!(values[*got], top, "mask = {:b}, values = {:?}", mask, values);
+            }
+        }
+        assert_eq!(best_submasks(&[1, 3, 0, 2]), vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn masks_with_bits_match_brute_force() {
+        for n in 0..=10 {
+            for k in 0..=n + 2 {
+                let want: Vec<u64> = (0..1u64 << n).filter(|m| m.count_ones() == k).collect();
+                assert_eq!(masks_with_bits(n, k).collect::<Vec<_>>(), want, "n = {}, k = {}", n, k);
+            }
+        }
+        assert_eq!(masks_with_bits(63, 0).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(masks_with_bits(63, 1).count(), 63);
+        assert_eq!(masks_with_bits(63, 62).count(), 63);
+        assert_eq!(masks_with_bits(63, 63).collect::<Vec<_>>(), vec![u64::MAX >> 1]);
+    }
+
+    #[test]
+    fn bits_match_brute_force() {
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        let mut masks = vec![0, 1, u64::MAX, 1 << 63];
+        masks.extend((0..200).map(|_| xorshift(&mut seed)));
+        for mask in masks {
+            let want: Vec<u32> = (0..64).filter(|b| mask & (1 << b) != 0).collect();
+            assert_eq!(bits(mask).collect::<Vec<_>>(), want, "mask = {:x}", mask);
+        }
+    }
+
+    /// Check an answer from k_sum, or that there really is none.
+    fn check_k_sum<T>(items: &[T], k: usize, target: T, want: bool)
+    where T: Copy + Into<i128> + std::fmt::Debug {
+        match k_sum(items, k, target) {
+            Some(idx) => {
+                let mut distinct = idx.clone();
+                distinct.sort();
+                distinct.dedup();
+                assert_eq!(distinct.len(), k, "{:?} from {:?}", idx, items);
+                assert_eq!(idx.iter().map(|i| items[*i].into()).sum::<i128>(), target.into());
+            }
+            None => assert!(!want, "no {}-sum to {:?} found in {:?}", k, target, items),
+        }
+    }
+
+    #[test]
+    fn k_sum_matches_brute_force() {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        for _ in 0..300 {
+            let n = (xorshift(&mut seed) % 9) as usize;
+            let items: Vec<i32> = (0..n).map(|_| (xorshift(&mut seed) % 21) as i32 - 10).collect();
+            let unsigned: Vec<u32> = items.iter().map(|v| v.unsigned_abs()).collect();
+            for k in 1..=4 {
+                for target in -15..=15 {
+                    let want = combinations(&items, k).any(|c| c.into_iter().sum::<i32>() == target);
+                    check_k_sum(&items, k, target, want);
+                }
+                for target in 0..=25 {
+                    let want = combinations(&unsigned, k).any(|c| c.into_iter().sum::<u32>() == target);
+                    check_k_sum(&unsigned, k, target, want);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn k_sum_near_the_limits() {
+        assert_eq!(k_sum(&[5u32, 1], 2, 3), None);
+        assert_eq!(k_sum(&[5u32, 1, 4], 3, 3), None);
+        assert_eq!(k_sum(&[u32::MAX, 1, 2], 2, 3), Some(vec![1, 2]));
+        check_k_sum(&[u32::MAX, u32::MAX, 0, 1, 2], 3, 3, true);
+        check_k_sum(&[u8::MAX, 200, 52, 0, 3], 3, 255, true);
+        check_k_sum(&[i8::MIN, i8::MIN, i8::MAX, i8::MAX, 0, 1], 3, 0, true);
+        check_k_sum(&[i8::MIN, i8::MAX, i8::MAX, 0], 3, 126, true);
+        check_k_sum(&[i8::MIN, 0, 5, i8::MAX], 3, -1, true);
+        check_k_sum(&[i64::MIN, i64::MAX, u8::MAX as i64, 2], 3, 1, true);
+        assert_eq!(k_sum(&[i64::MIN, -1], 2, i64::MAX), None);
+        assert_eq!(k_sum(&[u64::MAX, 1], 2, 0), None);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::voxel::Point3;
+
+/// Facings are numbered like 2022 day 22: right, down, left, up. Coordinates
+/// are (x, y) with y increasing downward.
+pub const FACINGS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// Where a face of the net ended up on the folded cube. `right` and `down` are
+/// the 3D directions of the face's x and y axes on the net, and `normal` points
+/// out of the cube.
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    block: (isize, isize),
+    right: Point3,
+    down: Point3,
+    normal: Point3,
+}
+
+/*
+ * How folding works: put the first face flat, then walk across the net. Each
+ * time we cross an edge onto a new face, that face is folded 90 degrees away
+ * from the one we came from, so its axes can be computed from ours. For
+ * instance, the face to the right of us has its normal pointing along our
+ * "right" axis, and its own "right" axis points back down into the cube.
+ *
+ * To wrap a position around an edge, we place the cell in 3D (using doubled
+ * coordinates so cell centres are integers), step it over the edge, and then
+ * project it onto whichever face now has the matching normal.
+ */
+
+/// A cube folded from a net of six square faces of equal size.
+#[derive(Clone, Debug)]
+pub struct CubeNet {
+    size: isize,
+    faces: Vec<Face>,
+    by_block: HashMap<(isize, isize), usize>,
+}
+
+impl CubeNet {
+    /// Fold the net made of every cell for which `on_map(x, y)` is true, in a
+    /// `width` x `height` area, with faces of `size` x `size` cells. Fails if
+    /// the cells don't form one of the 11 cube nets.
+    pub fn new<F>(width: usize, height: usize, size: usize, on_map: F) -> Result<CubeNet, String>
+    where F: Fn(usize, usize) -> bool {
+        if size == 0 {
+            return Err("face size must be positive".into());
+        }
+        let mut blocks = Vec::new();
+        for by in 0..height / size {
+            for bx in 0..width / size {
+                if on_map(bx * size, by * size) {
+                    blocks.push((bx as isize, by as isize));
+                }
+            }
+        }
+        if blocks.len() != 6 {
+            return Err(format!("a cube net needs 6 faces, found {}", blocks.len()));
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let block = ((x / size) as isize, (y / size) as isize);
+                if on_map(x, y) != blocks.contains(&block) {
+                    return Err(format!("cell ({}, {}) doesn't line up with the faces", x, y));
+                }
+            }
+        }
+
+        let mut faces: Vec<Face> = Vec::new();
+        let mut by_block: HashMap<(isize, isize), usize> = HashMap::new();
+        let mut q: VecDeque<Face> = VecDeque::new();
+        q.push_back(Face{
+            block: blocks[0],
+            right: Point3::new(1, 0, 0),
+            down: Point3::new(0, 1, 0),
+            normal: Point3::new(0, 0, -1),
+        });
+        while let Some(face) = q.pop_front() {
+            if by_block.contains_key(&face.block) {
+                continue;
+            }
+            by_block.insert(face.block, faces.len());
+            faces.push(face);
+            for (facing, (dx, dy)) in FACINGS.iter().enumerate() {
+                let block = (face.block.0 + dx, face.block.1 + dy);
+                if !blocks.contains(&block) || by_block.contains_key(&block) {
+                    continue;
+                }
+                let (right, down, normal) = (face.right, face.down, face.normal);
+                let next = match facing {
+                    0 => Face{block, right: -normal, down, normal: right},
+                    1 => Face{block, right, down: -normal, normal: down},
+                    2 => Face{block, right: normal, down, normal: -right},
+                    _ => Face{block, right, down: normal, normal: -down},
+                };
+                q.push_back(next);
+            }
+        }
+        if faces.len() != 6 {
+            return Err("the faces of the net are not all connected".into());
+        }
+        for (i, a) in faces.iter().enumerate() {
+            if faces[i + 1..].iter().any(|b| b.normal == a.normal) {
+                return Err("the net folds two faces onto the same side of the cube".into());
+            }
+        }
+        Ok(CubeNet{size: size as isize, faces, by_block})
+    }
+
+    pub fn size(&self) -> usize {
+        self.size as usize
+    }
+
+    /// Take one step from (x, y) in the direction of `facing`, following the
+    /// surface of the folded cube, and return the new position and facing.
+    /// Panics if (x, y) is not on the net.
+    pub fn wrap(&self, x: isize, y: isize, facing: usize) -> (isize, isize, usize) {
+        let n = self.size;
+        let face = &self.faces[self.face_index(x, y).expect("position is not on the cube net")];
+        let (u, v) = (x - face.block.0 * n, y - face.block.1 * n);
+        let (du, dv) = FACINGS[facing];
+        if (0..n).contains(&(u + du)) && (0..n).contains(&(v + dv)) {
+            /* Not leaving this face, so nothing to fold. */
+            return (x + du, y + dv, facing);
+        }
+
+        /* 3D position of the cell centre, in doubled coordinates */
+        let pos = face.normal.scale(n)
+            + face.right.scale(2 * u + 1 - n)
+            + face.down.scale(2 * v + 1 - n);
+        let dir = face.right.scale(du) + face.down.scale(dv);
+        /* Step over the edge: one half-cell out, and one half-cell down the side */
+        let pos = pos + dir - face.normal;
+        let next = self.faces.iter().find(|f| f.normal == dir).unwrap();
+        let u = (pos.dot(&next.right) + n - 1) / 2;
+        let v = (pos.dot(&next.down) + n - 1) / 2;
+        let heading = -face.normal;
+        let facing = FACINGS
+            .iter()
+            .position(|(du, dv)| next.right.scale(*du) + next.down.scale(*dv) == heading)
+            .unwrap();
+        (next.block.0 * n + u, next.block.1 * n + v, facing)
+    }
+
+    fn face_index(&self, x: isize, y: isize) -> Option<usize> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.by_block.get(&(x / self.size, y / self.size)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// The 11 nets of the cube, one '#' per face.
+    const NETS: [&str; 11] = [
+        "#...\n####\n#...",
+        "#...\n####\n.#..",
+        "#...\n####\n..#.",
+        "#...\n####\n...#",
+        ".#..\n####\n.#..",
+        ".#..\n####\n..#.",
+        "##..\n.###\n.#..",
+        "##..\n.###\n..#.",
+        "##..\n.###\n...#",
+        "##..\n.##.\n..##",
+        "###..\n..###",
+    ];
+
+    fn parse_blocks(art: &str) -> Vec<(isize, isize)> {
+        let mut blocks = Vec::new();
+        for (y, line) in art.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    blocks.push((x as isize, y as isize));
+                }
+            }
+        }
+        blocks
+    }
+
+    /// All 8 rotations and reflections, moved back to start at (0, 0).
+    fn orientations(blocks: &[(isize, isize)]) -> Vec<Vec<(isize, isize)>> {
+        let mut res = Vec::new();
+        for flip in [false, true] {
+            let mut cur: Vec<(isize, isize)> = blocks
+                .iter()
+                .map(|&(x, y)| if flip { (-x, y) } else { (x, y) })
+                .collect();
+            for _ in 0..4 {
+                cur = cur.iter().map(|&(x, y)| (-y, x)).collect();
+                let min_x = cur.iter().map(|b| b.0).min().unwrap();
+                let min_y = cur.iter().map(|b| b.1).min().unwrap();
+                res.push(cur.iter().map(|&(x, y)| (x - min_x, y - min_y)).collect());
+            }
+        }
+        res
+    }
+
+    fn fold(blocks: &[(isize, isize)], size: usize) -> Result<CubeNet, String> {
+        let set: HashSet<(isize, isize)> = blocks.iter().copied().collect();
+        let width = (blocks.iter().map(|b| b.0).max().unwrap() as usize + 1) * size;
+        let height = (blocks.iter().map(|b| b.1).max().unwrap() as usize + 1) * size;
+        CubeNet::new(width, height, size, |x, y| set.contains(&((x / size) as isize, (y / size) as isize)))
+    }
+
+    fn cells(blocks: &[(isize, isize)], size: isize) -> Vec<(isize, isize)> {
+        let mut res = Vec::new();
+        for (bx, by) in blocks {
+            for v in 0..size {
+                for u in 0..size {
+                    res.push((bx * size + u, by * size + v));
+                }
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn every_net_round_trips_in_every_orientation() {
+        for net in NETS {
+            for blocks in orientations(&parse_blocks(net)) {
+                for size in [1, 2, 3, 4] {
+                    let cube = fold(&blocks, size).unwrap_or_else(|e| panic!("{}: {}", net, e));
+                    let on_net: HashSet<(isize, isize)> = cells(&blocks, size as isize).into_iter().collect();
+                    for &(x, y) in on_net.iter() {
+                        for facing in 0..4 {
+                            let (nx, ny, nf) = cube.wrap(x, y, facing);
+                            assert!(on_net.contains(&(nx, ny)), "{}: ({}, {}) {} left the net", net, x, y, facing);
+                            /* Turning around and stepping back undoes the step. */
+                            let back = cube.wrap(nx, ny, (nf + 2) % 4);
+                            assert_eq!(back, (x, y, (facing + 2) % 4), "{}: ({}, {}) facing {}", net, x, y, facing);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walking_straight_circles_the_cube() {
+        for net in NETS {
+            let blocks = parse_blocks(net);
+            let size = 3;
+            let cube = fold(&blocks, size).unwrap();
+            for (x, y) in cells(&blocks, size as isize) {
+                for facing in 0..4 {
+                    let mut pos = (x, y, facing);
+                    for _ in 0..4 * size {
+                        pos = cube.wrap(pos.0, pos.1, pos.2);
+                    }
+                    assert_eq!(pos, (x, y, facing), "{}", net);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_non_nets() {
+        for art in ["######", "###\n###", "####\n.##.", "#####\n#....", "##.\n##.\n.##"] {
+            assert!(fold(&parse_blocks(art), 2).is_err(), "{}", art);
+        }
+        /* Five faces, or faces that don't line up with the grid. */
+        assert!(fold(&parse_blocks("#...\n####"), 2).is_err());
+        assert!(CubeNet::new(8, 6, 2, |x, y| (2..4).contains(&y) || x < 2 && y < 5).is_err());
+        assert!(CubeNet::new(4, 3, 0, |_, _| true).is_err());
+    }
+}
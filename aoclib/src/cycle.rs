@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A repeating sequence of states: the state after `start + len` steps is the
+/// same as the state after `start` steps, and so on forever after.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CycleInfo {
+    pub start: usize,
+    pub len: usize,
+}
+
+impl CycleInfo {
+    /// The earliest step whose state matches the state after `n` steps.
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.len
+        }
+    }
+}
+
+/// Brent's algorithm: find the cycle using only equality on states, and O(1)
+/// memory. Only suitable when comparing full states is cheap.
+pub fn brent<S, F>(init: S, mut step: F) -> CycleInfo
+where S: Clone + PartialEq,
+      F: FnMut(&S) -> S {
+    /* Find the cycle length by racing a hare ahead of a tortoise which
+     * teleports to the hare at each power of two. */
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = init.clone();
+    let mut hare = step(&init);
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        hare = step(&hare);
+        len += 1;
+    }
+    /* Then find the start by walking two pointers `len` apart. */
+    let mut tortoise = init.clone();
+    let mut hare = init;
+    for _ in 0..len {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    CycleInfo{start, len}
+}
+
+/// Run `step` until the fingerprint returned by `key` repeats. Returns the
+/// cycle along with every state before the repeat, so `history[c.reduce(n)]`
+/// is the state after `n` steps.
+pub fn find_cycle<S, K, FS, FK>(init: S, mut step: FS, mut key: FK) -> (CycleInfo, Vec<S>)
+where K: Eq + Hash,
+      FS: FnMut(&S) -> S,
+      FK: FnMut(&S) -> K {
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    let mut history = vec![init];
+    loop {
+        let cur = history.last().unwrap();
+        let n = history.len() - 1;
+        if let Some(start) = seen.insert(key(cur), n) {
+            history.pop();
+            return (CycleInfo{start, len: n - start}, history);
+        }
+        let next = step(cur);
+        history.push(next);
+    }
+}
+
+/// Compute `metric` of the state after `n` steps, without simulating all of
+/// them. The simulation runs until the fingerprint repeats, and from then on
+/// the metric is assumed to grow by the same amount every cycle (like the
+/// height of a tower of falling rocks). Unlike `find_cycle()`, no history of
+/// states is kept, so `step` updates the state in place.
+pub fn extrapolate<S, K, FS, FK, FM>(mut state: S, mut step: FS, mut key: FK, mut metric: FM, n: usize) -> i64
+where K: Eq + Hash,
+      FS: FnMut(&mut S),
+      FK: FnMut(&S) -> K,
+      FM: FnMut(&S) -> i64 {
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    let mut metrics: Vec<i64> = Vec::new();
+    loop {
+        let i = metrics.len();
+        metrics.push(metric(&state));
+        if i == n {
+            return metrics[i];
+        }
+        if let Some(start) = seen.insert(key(&state), i) {
+            let cycle = CycleInfo{start, len: i - start};
+            let per_cycle = metrics[i] - metrics[start];
+            let cycles = ((n - start) / cycle.len) as i64;
+            return metrics[cycle.reduce(n)] + cycles * per_cycle;
+        }
+        step(&mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The sequence x, f(x), f(f(x)), ... for a random f on 0..size, along
+    /// with its cycle worked out by remembering every state.
+    fn random_sequence(seed: &mut u64, size: usize) -> (Vec<usize>, CycleInfo) {
+        let f: Vec<usize> = (0..size).map(|_| {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            (*seed % size as u64) as usize
+        }).collect();
+        let mut seq = vec![0];
+        loop {
+            let next = f[*seq.last().unwrap()];
+            if let Some(start) = seq.iter().position(|x| *x == next) {
+                return (f, CycleInfo{start, len: seq.len() - start});
+            }
+            seq.push(next);
+        }
+    }
+
+    #[test]
+    fn brent_and_find_cycle_match_simulation() {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        for size in 1..60 {
+            for _ in 0..20 {
+                let (f, want) = random_sequence(&mut seed, size);
+                assert_eq!(brent(0, |x| f[*x]), want);
+                let (found, history) = find_cycle(0, |x| f[*x], |x| *x);
+                assert_eq!(found, want);
+                assert_eq!(history.len(), want.start + want.len);
+                let mut x = 0;
+                for n in 0..3 * size {
+                    assert_eq!(history[found.reduce(n)], x);
+                    x = f[x];
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cycles_from_the_start_or_after_a_prefix() {
+        /* 0 -> 1 -> 2 -> 0 */
+        let rotate = |x: &u32| (x + 1) % 3;
+        assert_eq!(brent(0, rotate), CycleInfo{start: 0, len: 3});
+        assert_eq!(find_cycle(0, rotate, |x| *x).0, CycleInfo{start: 0, len: 3});
+        /* 10 -> 9 -> ... -> 3 -> 2 -> 3 -> 2 */
+        let fall = |x: &u32| if *x > 2 { x - 1 } else { 3 };
+        assert_eq!(brent(10, fall), CycleInfo{start: 7, len: 2});
+        assert_eq!(find_cycle(10, fall, |x| *x).0, CycleInfo{start: 7, len: 2});
+        /* A fixed point straight away. */
+        assert_eq!(brent(5, |x: &u32| *x), CycleInfo{start: 0, len: 1});
+        let c = CycleInfo{start: 7, len: 2};
+        assert_eq!((c.reduce(3), c.reduce(7), c.reduce(8), c.reduce(9), c.reduce(1000)), (3, 7, 8, 7, 8));
+    }
+
+    #[test]
+    fn extrapolate_matches_simulation() {
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        for size in 1..40 {
+            let (f, _) = random_sequence(&mut seed, size);
+            /* The state is a position and a running total of positions seen,
+             * which grows by the same amount every time round the cycle. */
+            let step = |s: &mut (usize, i64)| {
+                s.0 = f[s.0];
+                s.1 += s.0 as i64;
+            };
+            let mut naive = (0, 0);
+            for n in 0..=5000 {
+                if n < 4 * size || n % 997 == 0 {
+                    let got = extrapolate((0, 0), step, |s| s.0, |s| s.1, n);
+                    assert_eq!(got, naive.1, "size {}, n {}", size, n);
+                }
+                step(&mut naive);
+            }
+        }
+        /* Counting up by one with a key that cycles from the start. */
+        let count = extrapolate(0i64, |s| *s += 1, |s| s % 5, |s| *s, 1_000_000_007);
+        assert_eq!(count, 1_000_000_007);
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::bigint::BigInt;
+use crate::poly::RationalFunction;
+use crate::rational::Rational;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    pub fn from_symbol(s: &str) -> Option<BinOp> {
+        match s {
+            "+" => Some(BinOp::Add),
+            "-" => Some(BinOp::Sub),
+            "*" => Some(BinOp::Mul),
+            "/" => Some(BinOp::Div),
+            _ => None,
+        }
+    }
+}
+
+/// Values which an expression graph can compute with. `apply` returns None
+/// when the operation fails (overflow, division by zero, and so on).
+pub trait Value: Clone + PartialEq + Sized {
+    fn apply(op: BinOp, a: &Self, b: &Self) -> Option<Self>;
+
+    /// Find `a` such that `a op b == target`.
+    fn solve_left(op: BinOp, b: &Self, target: &Self) -> Option<Self> {
+        let a = match op {
+            BinOp::Add => Self::apply(BinOp::Sub, target, b)?,
+            BinOp::Sub => Self::apply(BinOp::Add, target, b)?,
+            BinOp::Mul => Self::apply(BinOp::Div, target, b)?,
+            BinOp::Div => Self::apply(BinOp::Mul, target, b)?,
+        };
+        /* Integer division can make the inverse inexact, so double check. */
+        (Self::apply(op, &a, b).as_ref() == Some(target)).then_some(a)
+    }
+
+    /// Find `b` such that `a op b == target`.
+    fn solve_right(op: BinOp, a: &Self, target: &Self) -> Option<Self> {
+        let b = match op {
+            BinOp::Add => Self::apply(BinOp::Sub, target, a)?,
+            BinOp::Sub => Self::apply(BinOp::Sub, a, target)?,
+            BinOp::Mul => Self::apply(BinOp::Div, target, a)?,
+            BinOp::Div => Self::apply(BinOp::Div, a, target)?,
+        };
+        (Self::apply(op, a, &b).as_ref() == Some(target)).then_some(b)
+    }
+}
+
+impl Value for isize {
+    fn apply(op: BinOp, a: &isize, b: &isize) -> Option<isize> {
+        match op {
+            BinOp::Add => a.checked_add(*b),
+            BinOp::Sub => a.checked_sub(*b),
+            BinOp::Mul => a.checked_mul(*b),
+            BinOp::Div => a.checked_div(*b),
+        }
+    }
+}
+
+impl Value for BigInt {
+    fn apply(op: BinOp, a: &BigInt, b: &BigInt) -> Option<BigInt> {
+        match op {
+            BinOp::Add => Some(a + b),
+            BinOp::Sub => Some(a - b),
+            BinOp::Mul => Some(a * b),
+            BinOp::Div if b.is_zero() => None,
+            BinOp::Div => Some(a / b),
+        }
+    }
+}
+
+impl Value for Rational {
+    fn apply(op: BinOp, a: &Rational, b: &Rational) -> Option<Rational> {
+        match op {
+            BinOp::Add => Some(a + b),
+            BinOp::Sub => Some(a - b),
+            BinOp::Mul => Some(a * b),
+            BinOp::Div if b.is_zero() => None,
+            BinOp::Div => Some(a / b),
+        }
+    }
+}
+
+impl Value for RationalFunction {
+    fn apply(op: BinOp, a: &RationalFunction, b: &RationalFunction) -> Option<RationalFunction> {
+        match op {
+            BinOp::Add => Some(a + b),
+            BinOp::Sub => Some(a - b),
+            BinOp::Mul => Some(a * b),
+            BinOp::Div if b.numerator().is_zero() => None,
+            BinOp::Div => Some(a / b),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node<K, V> {
+    Value(V),
+    Op(BinOp, K, K),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExprError<K> {
+    /// A node refers to a name which was never defined.
+    Missing(K),
+    /// The nodes form a cycle; the first node is repeated at the end.
+    Cycle(Vec<K>),
+    /// Applying the operator at this node failed.
+    Arithmetic(K),
+    /// The unknown does not appear below the node being solved.
+    NotFound(K),
+    /// The unknown appears on both sides of this node, so it can't be inverted.
+    BothSides(K),
+    /// No value for the operand below this node gives the required result.
+    NoSolution(K),
+}
+
+impl<K> ExprError<K> {
+    /// Convert the keys, e.g. from interned ids back to names for display.
+    pub fn map_keys<J, F: FnMut(K) -> J>(self, mut f: F) -> ExprError<J> {
+        match self {
+            ExprError::Missing(k) => ExprError::Missing(f(k)),
+            ExprError::Cycle(path) => ExprError::Cycle(path.into_iter().map(f).collect()),
+            ExprError::Arithmetic(k) => ExprError::Arithmetic(f(k)),
+            ExprError::NotFound(k) => ExprError::NotFound(f(k)),
+            ExprError::BothSides(k) => ExprError::BothSides(f(k)),
+            ExprError::NoSolution(k) => ExprError::NoSolution(f(k)),
+        }
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for ExprError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::Missing(k) => write!(f, "node {:?} is not defined", k),
+            ExprError::Cycle(path) => write!(f, "expression contains a cycle: {:?}", path),
+            ExprError::Arithmetic(k) => write!(f, "arithmetic error evaluating {:?}", k),
+            ExprError::NotFound(k) => write!(f, "unknown does not appear below {:?}", k),
+            ExprError::BothSides(k) => write!(f, "unknown appears on both sides of {:?}", k),
+            ExprError::NoSolution(k) => write!(f, "no solution when inverting {:?}", k),
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for ExprError<K> {}
+
+/// A set of named nodes, each either a value or an operator applied to two
+/// other nodes. Evaluation uses an explicit stack and caches every result, so
+/// long chains and shared subexpressions are both cheap.
+#[derive(Clone, Debug)]
+pub struct ExprGraph<K, V> {
+    nodes: HashMap<K, Node<K, V>>,
+}
+
+impl<K, V> Default for ExprGraph<K, V> {
+    fn default() -> Self {
+        ExprGraph{nodes: HashMap::new()}
+    }
+}
+
+impl<K, V> ExprGraph<K, V>
+where K: Clone + Eq + Hash,
+      V: Value {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: K, node: Node<K, V>) {
+        self.nodes.insert(name, node);
+    }
+
+    pub fn get(&self, name: &K) -> Option<&Node<K, V>> {
+        self.nodes.get(name)
+    }
+
+    /// Convert every value node, keeping the structure the same.
+    pub fn map_values<W, F>(&self, mut f: F) -> ExprGraph<K, W>
+    where F: FnMut(&K, &V) -> W {
+        let nodes = self.nodes.iter().map(|(k, node)| {
+            let node = match node {
+                Node::Value(v) => Node::Value(f(k, v)),
+                Node::Op(op, a, b) => Node::Op(*op, a.clone(), b.clone()),
+            };
+            (k.clone(), node)
+        }).collect();
+        ExprGraph{nodes}
+    }
+
+    /// Evaluate everything below `root` into `cache`. If `unknown` is given, it
+    /// has no value: it and every node depending on it go into `tainted`.
+    fn eval_into(
+        &self,
+        root: &K,
+        unknown: Option<&K>,
+        cache: &mut HashMap<K, V>,
+        tainted: &mut HashSet<K>,
+    ) -> Result<(), ExprError<K>> {
+        let mut stack: Vec<(K, bool)> = vec![(root.clone(), false)];
+        /* The operators being expanded, root first, and the same as a set. */
+        let mut path: Vec<K> = Vec::new();
+        let mut on_path: HashSet<K> = HashSet::new();
+        while let Some((name, expanded)) = stack.pop() {
+            if cache.contains_key(&name) || tainted.contains(&name) {
+                continue;
+            }
+            if unknown == Some(&name) {
+                tainted.insert(name);
+                continue;
+            }
+            let node = self.nodes.get(&name).ok_or_else(|| ExprError::Missing(name.clone()))?;
+            match node {
+                Node::Value(v) => {
+                    cache.insert(name, v.clone());
+                }
+                Node::Op(op, a, b) if expanded => {
+                    on_path.remove(&path.pop().unwrap());
+                    if tainted.contains(a) || tainted.contains(b) {
+                        tainted.insert(name);
+                        continue;
+                    }
+                    let val = V::apply(*op, &cache[a], &cache[b])
+                        .ok_or_else(|| ExprError::Arithmetic(name.clone()))?;
+                    cache.insert(name, val);
+                }
+                Node::Op(_, a, b) => {
+                    for child in [a, b] {
+                        if on_path.contains(child) {
+                            let pos = path.iter().position(|n| n == child).unwrap();
+                            let mut cycle = path[pos..].to_vec();
+                            cycle.push(name.clone());
+                            cycle.push(child.clone());
+                            return Err(ExprError::Cycle(cycle));
+                        }
+                        if *child == name {
+                            return Err(ExprError::Cycle(vec![name.clone(), name.clone()]));
+                        }
+                    }
+                    path.push(name.clone());
+                    on_path.insert(name.clone());
+                    stack.push((name.clone(), true));
+                    stack.push((b.clone(), false));
+                    stack.push((a.clone(), false));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn eval(&self, root: &K) -> Result<V, ExprError<K>> {
+        let mut cache = HashMap::new();
+        self.eval_into(root, None, &mut cache, &mut HashSet::new())?;
+        Ok(cache.remove(root).unwrap())
+    }
+
+    /// Find the value of `unknown` which makes `root` evaluate to `target`,
+    /// ignoring whatever value `unknown` currently holds. This works backward
+    /// from the root, inverting one operator at a time, so the unknown must
+    /// only be used once on the way down.
+    pub fn solve(&self, root: &K, target: V, unknown: &K) -> Result<V, ExprError<K>> {
+        let mut cache = HashMap::new();
+        let mut tainted = HashSet::new();
+        self.eval_into(root, Some(unknown), &mut cache, &mut tainted)?;
+        if !tainted.contains(root) {
+            return Err(ExprError::NotFound(root.clone()));
+        }
+        let mut cur = root.clone();
+        let mut target = target;
+        while cur != *unknown {
+            let (op, a, b) = match &self.nodes[&cur] {
+                Node::Op(op, a, b) => (*op, a, b),
+                Node::Value(_) => unreachable!("only operators can depend on the unknown"),
+            };
+            let next = match (tainted.contains(a), tainted.contains(b)) {
+                (true, true) => return Err(ExprError::BothSides(cur)),
+                (true, false) => (a, V::solve_left(op, &cache[b], &target)),
+                (false, true) => (b, V::solve_right(op, &cache[a], &target)),
+                (false, false) => unreachable!("tainted node with untainted operands"),
+            };
+            target = next.1.ok_or_else(|| ExprError::NoSolution(cur.clone()))?;
+            cur = next.0.clone();
+        }
+        Ok(target)
+    }
+
+    /// Find the value of `unknown` which makes both operands of `root` equal.
+    pub fn solve_equal(&self, root: &K, unknown: &K) -> Result<V, ExprError<K>> {
+        let (a, b) = match self.nodes.get(root) {
+            Some(Node::Op(_, a, b)) => (a, b),
+            Some(Node::Value(_)) => return Err(ExprError::NotFound(root.clone())),
+            None => return Err(ExprError::Missing(root.clone())),
+        };
+        /* Whichever side evaluates without the unknown is the target. */
+        match self.eval_partial(b, unknown)? {
+            Some(target) => self.solve(a, target, unknown),
+            None => match self.eval_partial(a, unknown)? {
+                Some(target) => self.solve(b, target, unknown),
+                None => Err(ExprError::BothSides(root.clone())),
+            },
+        }
+    }
+
+    fn eval_partial(&self, root: &K, unknown: &K) -> Result<Option<V>, ExprError<K>> {
+        let mut cache = HashMap::new();
+        self.eval_into(root, Some(unknown), &mut cache, &mut HashSet::new())?;
+        Ok(cache.remove(root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a graph from lines like "a: b + c" and "b: 4".
+    fn graph<V: Value + From<i16>>(src: &str) -> ExprGraph<&str, V> {
+        let mut g = ExprGraph::new();
+        for line in src.lines() {
+            let (name, rest) = line.trim().split_once(": ").unwrap();
+            let tokens: Vec<&str> = rest.split(' ').collect();
+            let node = match tokens[..] {
+                [a, op, b] => Node::Op(BinOp::from_symbol(op).unwrap(), a, b),
+                [v] => Node::Value(V::from(v.parse::<i16>().unwrap())),
+                _ => panic!("bad line {}", line),
+            };
+            g.insert(name, node);
+        }
+        g
+    }
+
+    const MONKEYS: &str = "root: pppw + sjmn
+        dbpl: 5
+        cczh: sllz + lgvd
+        zczc: 2
+        ptdq: humn - dvpt
+        dvpt: 3
+        lfqf: 4
+        humn: 5
+        ljgn: 2
+        sjmn: drzm * dbpl
+        sllz: 4
+        pppw: cczh / lfqf
+        lgvd: ljgn * ptdq
+        drzm: hmdt - zczc
+        hmdt: 32";
+
+    #[test]
+    fn eval_and_solve() {
+        let g: ExprGraph<&str, isize> = graph(MONKEYS);
+        assert_eq!(g.eval(&"root"), Ok(152));
+        assert_eq!(g.eval(&"lgvd"), Ok(4));
+        assert_eq!(g.solve_equal(&"root", &"humn"), Ok(301));
+        /* Whatever humn held before is ignored. */
+        assert_eq!(g.solve(&"lgvd", 10, &"humn"), Ok(8));
+        assert_eq!(g.solve(&"sjmn", 10, &"humn"), Err(ExprError::NotFound("sjmn")));
+        let big: ExprGraph<&str, BigInt> = graph(MONKEYS);
+        assert_eq!(big.solve_equal(&"root", &"humn"), Ok(BigInt::from(301)));
+    }
+
+    #[test]
+    fn solve_equal_with_the_unknown_on_either_side() {
+        let left: ExprGraph<&str, Rational> = graph("r: a - b\na: x / c\nb: 7\nc: 2\nx: 0");
+        assert_eq!(left.solve_equal(&"r", &"x"), Ok(Rational::from(14)));
+        let right: ExprGraph<&str, Rational> = graph("r: b - a\na: c / x\nb: 7\nc: 2\nx: 0");
+        assert_eq!(right.solve_equal(&"r", &"x"), Ok(Rational::new(BigInt::from(2), BigInt::from(7))));
+        let both: ExprGraph<&str, Rational> = graph("r: a + b\na: x * c\nb: x - z\nc: 2\nx: 0\nz: 0");
+        assert_eq!(both.solve_equal(&"r", &"x"), Err(ExprError::BothSides("r")));
+        let squared: ExprGraph<&str, Rational> = graph("r: a + c\na: x * x\nc: 2\nx: 0");
+        assert_eq!(squared.solve(&"r", Rational::from(6), &"x"), Err(ExprError::BothSides("a")));
+        assert_eq!(squared.solve_equal(&"c", &"x"), Err(ExprError::NotFound("c")));
+    }
+
+    #[test]
+    fn inexact_division_is_rejected() {
+        /* 3 * x = 7 has no integer solution, though 7 / 3 rounds to one. */
+        let g: ExprGraph<&str, isize> = graph("r: t * x\nt: 3\nx: 0");
+        assert_eq!(g.solve(&"r", 7, &"x"), Err(ExprError::NoSolution("r")));
+        assert_eq!(g.solve(&"r", 9, &"x"), Ok(3));
+        /* 20 / x = 6 is solved by x = 3 under integer division, but nothing
+         * gives 20 / x = 7. */
+        let d: ExprGraph<&str, isize> = graph("r: t / x\nt: 20\nx: 0");
+        assert_eq!(d.solve(&"r", 6, &"x"), Ok(3));
+        assert_eq!(d.solve(&"r", 7, &"x"), Err(ExprError::NoSolution("r")));
+        /* Exact values have no such trouble. */
+        let q: ExprGraph<&str, Rational> = graph("r: t * x\nt: 3\nx: 0");
+        assert_eq!(q.solve(&"r", Rational::from(7), &"x"), Ok(Rational::new(BigInt::from(7), BigInt::from(3))));
+    }
+
+    #[test]
+    fn errors() {
+        let cycle: ExprGraph<&str, isize> = graph("a: b + c\nb: c * d\nc: 1\nd: a - c");
+        assert_eq!(cycle.eval(&"a"), Err(ExprError::Cycle(vec!["a", "b", "d", "a"])));
+        let own: ExprGraph<&str, isize> = graph("a: a + b\nb: 1");
+        assert_eq!(own.eval(&"a"), Err(ExprError::Cycle(vec!["a", "a"])));
+        let missing: ExprGraph<&str, isize> = graph("a: b + c\nb: 1");
+        assert_eq!(missing.eval(&"a"), Err(ExprError::Missing("c")));
+        assert_eq!(missing.eval(&"z"), Err(ExprError::Missing("z")));
+        let zero: ExprGraph<&str, isize> = graph("a: b / c\nb: 1\nc: 0");
+        assert_eq!(zero.eval(&"a"), Err(ExprError::Arithmetic("a")));
+        let mut overflow: ExprGraph<&str, isize> = graph("a: b * b");
+        overflow.insert("b", Node::Value(isize::MAX / 2));
+        assert_eq!(overflow.eval(&"a"), Err(ExprError::Arithmetic("a")));
+        assert_eq!(ExprError::Cycle(vec!["a", "a"]).map_keys(str::len), ExprError::Cycle(vec![1, 1]));
+    }
+
+    #[test]
+    fn shared_subexpressions_and_long_chains() {
+        /* A diamond of shared nodes doubling 60 times would take 2^60 steps
+         * without the cache; a long chain would overflow a recursive stack. */
+        let mut g: ExprGraph<u32, BigInt> = ExprGraph::new();
+        g.insert(0, Node::Value(BigInt::one()));
+        for i in 1..=60 {
+            g.insert(i, Node::Op(BinOp::Add, i - 1, i - 1));
+        }
+        assert_eq!(g.eval(&60), Ok(BigInt::from(1u64 << 60)));
+        for i in 61..100_000 {
+            g.insert(i, Node::Op(BinOp::Sub, i - 1, 0));
+        }
+        assert_eq!(g.eval(&99_999), Ok(BigInt::from((1u64 << 60) - 99_939)));
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::search;
+
+/// Unweighted shortest path lengths between every pair of nodes, computed by
+/// running a BFS from each node. Pairs with no path are omitted.
+pub fn all_pairs_bfs<N, I, S, F>(nodes: S, mut neighbours: F) -> HashMap<N, HashMap<N, usize>>
+where N: Clone + Eq + Hash,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = N>,
+      F: FnMut(&N) -> I {
+    nodes
+        .into_iter()
+        .map(|start| {
+            let res = search::bfs([start.clone()], &mut neighbours, |_| false);
+            (start, res.dist)
+        })
+        .collect()
+}
+
+/// Floyd-Warshall over nodes numbered `0..n`. Edges are `(from, to, weight)`
+/// and are directed; add both directions for an undirected graph.
+pub fn floyd_warshall<I>(n: usize, edges: I) -> Vec<Vec<Option<usize>>>
+where I: IntoIterator<Item = (usize, usize, usize)> {
+    let mut dist = vec![vec![None; n]; n];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[i] = Some(0);
+    }
+    for (from, to, weight) in edges {
+        if dist[from][to].is_none_or(|d| weight < d) {
+            dist[from][to] = Some(weight);
+        }
+    }
+    for k in 0..n {
+        let row_k = dist[k].clone();
+        for row_i in dist.iter_mut() {
+            let ik = match row_i[k] {
+                Some(d) => d,
+                None => continue,
+            };
+            for (ij, kj) in row_i.iter_mut().zip(row_k.iter()) {
+                if let Some(kj) = kj {
+                    if ij.is_none_or(|d| ik + kj < d) {
+                        *ij = Some(ik + kj);
+                    }
+                }
+            }
+        }
+    }
+    dist
+}
+
+/// A graph reduced to a subset of "interesting" nodes, with weighted edges
+/// giving the shortest path length between them in the original graph.
+#[derive(Clone, Debug)]
+pub struct Contracted<N> {
+    pub nodes: Vec<N>,
+    pub index: HashMap<N, usize>,
+    pub dist: Vec<Vec<Option<usize>>>,
+}
+
+impl<N> Contracted<N> {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Reachable nodes from node `i`, other than itself, with their distance.
+    pub fn edges(&self, i: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.dist[i]
+            .iter()
+            .enumerate()
+            .filter_map(move |(j, d)| if i == j { None } else { d.map(|d| (j, d)) })
+    }
+}
+
+/// Contract a graph down to the nodes for which `keep` returns true. Nodes
+/// which are not kept are still traversed when measuring distances.
+pub fn contract<N, I, S, FN, FK>(nodes: S, mut neighbours: FN, mut keep: FK) -> Contracted<N>
+where N: Clone + Eq + Hash,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = N>,
+      FN: FnMut(&N) -> I,
+      FK: FnMut(&N) -> bool {
+    let nodes: Vec<N> = nodes.into_iter().filter(|n| keep(n)).collect();
+    let index: HashMap<N, usize> = nodes.iter().cloned().enumerate().map(|(i, n)| (n, i)).collect();
+    let mut dist = vec![vec![None; nodes.len()]; nodes.len()];
+    for (i, start) in nodes.iter().enumerate() {
+        let res = search::bfs([start.clone()], &mut neighbours, |_| false);
+        for (node, d) in res.dist {
+            if let Some(j) = index.get(&node) {
+                dist[i][*j] = Some(d);
+            }
+        }
+    }
+    Contracted{nodes, index, dist}
+}
+
+/// A cycle found while sorting a graph which should have been acyclic. The
+/// first node is repeated at the end, so `[a, b, a]` means `a -> b -> a`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle<N>(pub Vec<N>);
+
+impl<N: fmt::Debug> fmt::Display for Cycle<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph contains a cycle: ")?;
+        for (i, node) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{:?}", node)?;
+        }
+        Ok(())
+    }
+}
+
+impl<N: fmt::Debug> Error for Cycle<N> {}
+
+enum VisitState {
+    Visiting,
+    Seen,
+}
+
+/// Sort nodes so that each one comes after all of its successors (so a node's
+/// dependencies are always handled before it). Nodes reachable from `nodes` are
+/// included even if they weren't listed. Uses an explicit stack, so deep chains
+/// won't overflow.
+pub fn topological_sort<N, I, S, F>(nodes: S, mut successors: F) -> Result<Vec<N>, Cycle<N>>
+where N: Clone + Eq + Hash,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = N>,
+      F: FnMut(&N) -> I {
+    let mut output: Vec<N> = Vec::new();
+    let mut state: HashMap<N, VisitState> = HashMap::new();
+
+    for root in nodes {
+        if state.contains_key(&root) {
+            continue;
+        }
+        state.insert(root.clone(), VisitState::Visiting);
+        let mut stack = vec![(root.clone(), successors(&root).into_iter())];
+        while let Some((_, iter)) = stack.last_mut() {
+            match iter.next() {
+                Some(next) => match state.get(&next) {
+                    None => {
+                        state.insert(next.clone(), VisitState::Visiting);
+                        let iter = successors(&next).into_iter();
+                        stack.push((next, iter));
+                    }
+                    Some(VisitState::Visiting) => {
+                        let start = stack.iter().position(|(n, _)| *n == next).unwrap();
+                        let mut cycle: Vec<N> = stack.drain(start..).map(|(n, _)| n).collect();
+                        cycle.push(next);
+                        return Err(Cycle(cycle));
+                    }
+                    Some(VisitState::Seen) => {}
+                },
+                None => {
+                    let (node, _) = stack.pop().unwrap();
+                    state.insert(node.clone(), VisitState::Seen);
+                    output.push(node);
+                }
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Every node from which `target` can be reached by following successors,
+/// including `target` itself.
+pub fn reverse_reachable<N, I, S, F>(nodes: S, mut successors: F, target: &N) -> HashSet<N>
+where N: Clone + Eq + Hash,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = N>,
+      F: FnMut(&N) -> I {
+    let mut predecessors: HashMap<N, Vec<N>> = HashMap::new();
+    for node in nodes {
+        for next in successors(&node) {
+            predecessors.entry(next).or_default().push(node.clone());
+        }
+    }
+    let res = search::bfs(
+        [target.clone()],
+        |n| predecessors.get(n).cloned().unwrap_or_default(),
+        |_| false,
+    );
+    res.dist.into_keys().collect()
+}
+
+/// Compute a value for each node of a DAG, where each node's value may depend
+/// on the values of its successors. `f` is called once per node, in
+/// topological order, with the values computed so far (which always include
+/// every successor of the node).
+pub fn fold_dag<N, V, I, S, FS, FV>(nodes: S, successors: FS, mut f: FV) -> Result<HashMap<N, V>, Cycle<N>>
+where N: Clone + Eq + Hash,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = N>,
+      FS: FnMut(&N) -> I,
+      FV: FnMut(&N, &HashMap<N, V>) -> V {
+    let mut values: HashMap<N, V> = HashMap::new();
+    for node in topological_sort(nodes, successors)? {
+        let value = f(&node, &values);
+        values.insert(node, value);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small directed graph: a -> b -> c -> e, a -> d -> e, f -> a, and g
+    /// on its own.
+    fn dag(n: &char) -> Vec<char> {
+        match n {
+            'a' => vec!['b', 'd'],
+            'b' => vec!['c'],
+            'c' | 'd' => vec!['e'],
+            'f' => vec!['a'],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn all_pairs_bfs_omits_unreachable_pairs() {
+        let dist = all_pairs_bfs("abcdefg".chars(), dag);
+        assert_eq!(dist[&'a'][&'e'], 2);
+        assert_eq!(dist[&'f'][&'c'], 3);
+        assert_eq!(dist[&'g'].len(), 1);
+        assert_eq!(dist[&'e'].get(&'a'), None);
+        assert_eq!(dist[&'a'].get(&'f'), None);
+        assert_eq!(dist[&'c'][&'c'], 0);
+    }
+
+    #[test]
+    fn floyd_warshall_prefers_cheaper_routes() {
+        let edges = [(0, 1, 10), (0, 1, 8), (0, 2, 1), (2, 1, 2), (1, 3, 1), (3, 0, 4)];
+        let dist = floyd_warshall(5, edges);
+        assert_eq!(dist[0][1], Some(3));
+        assert_eq!(dist[0][3], Some(4));
+        assert_eq!(dist[1][0], Some(5));
+        assert_eq!(dist[3][2], Some(5));
+        assert_eq!(dist[4][4], Some(0));
+        assert_eq!(dist[0][4], None);
+        assert_eq!(dist[4][0], None);
+    }
+
+    #[test]
+    fn contract_keeps_distances_through_dropped_nodes() {
+        /* An undirected path 0 - 1 - 2 - 3 - 4, plus 5 - 6 off on their own. */
+        let neighbours = |i: &u32| match i {
+            0..=4 => [i.wrapping_sub(1), i + 1].into_iter().filter(|j| *j <= 4).collect(),
+            5 => vec![6],
+            6 => vec![5],
+            _ => vec![],
+        };
+        let c = contract(0..7u32, neighbours, |i| i % 2 == 0);
+        assert_eq!(c.nodes, vec![0, 2, 4, 6]);
+        assert_eq!(c.index[&4], 2);
+        assert_eq!(c.dist[0][2], Some(4));
+        assert_eq!(c.dist[c.index[&6]][0], None);
+        assert_eq!(c.edges(1).collect::<Vec<_>>(), vec![(0, 2), (2, 2)]);
+        assert_eq!(c.edges(3).count(), 0);
+        assert!(!c.is_empty());
+    }
+
+    #[test]
+    fn topological_sort_puts_successors_first() {
+        /* Only f is listed: everything reachable from it comes along. */
+        let order = topological_sort(['f', 'g'], dag).unwrap();
+        assert_eq!(order.len(), 7);
+        let pos = |n: char| order.iter().position(|m| *m == n).unwrap();
+        for n in "abcdefg".chars() {
+            for m in dag(&n) {
+                assert!(pos(m) < pos(n), "{} should come before {} in {:?}", m, n, order);
+            }
+        }
+        assert_eq!(topological_sort(['e'], dag), Ok(vec!['e']));
+    }
+
+    fn cyclic(n: &u32) -> Vec<u32> {
+        match n {
+            0 => vec![1],
+            1 => vec![2, 4],
+            2 => vec![3],
+            3 => vec![1],
+            5 => vec![5],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn topological_sort_reports_cycles() {
+        let err = topological_sort([0], cyclic).unwrap_err();
+        assert_eq!(err, Cycle(vec![1, 2, 3, 1]));
+        assert_eq!(err.to_string(), "graph contains a cycle: 1 -> 2 -> 3 -> 1");
+        assert_eq!(topological_sort([4, 5], cyclic), Err(Cycle(vec![5, 5])));
+        assert_eq!(topological_sort([4], cyclic), Ok(vec![4]));
+    }
+
+    #[test]
+    fn reverse_reachable_follows_edges_backward() {
+        let to_e = reverse_reachable("abcdefg".chars(), dag, &'e');
+        assert_eq!(to_e, "abcdef".chars().collect());
+        let to_b = reverse_reachable("abcdefg".chars(), dag, &'b');
+        assert_eq!(to_b, "abf".chars().collect());
+        let to_g = reverse_reachable("abcdefg".chars(), dag, &'g');
+        assert_eq!(to_g, HashSet::from(['g']));
+        /* Cycles are fine here: every node on one reaches the rest. */
+        let to_4 = reverse_reachable(0..6, cyclic, &4);
+        assert_eq!(to_4, HashSet::from([0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn fold_dag_sees_successors_first() {
+        /* The number of paths from each node down to a sink. */
+        let paths = fold_dag("abcdefg".chars(), dag, |n, done: &HashMap<char, u32>| {
+            let next = dag(n);
+            if next.is_empty() { 1 } else { next.iter().map(|m| done[m]).sum() }
+        }).unwrap();
+        assert_eq!((paths[&'f'], paths[&'a'], paths[&'b'], paths[&'g']), (2, 2, 1, 1));
+        let err = fold_dag([0], cyclic, |_, _: &HashMap<u32, u32>| 0).unwrap_err();
+        assert_eq!(err, Cycle(vec![1, 2, 3, 1]));
+    }
+}
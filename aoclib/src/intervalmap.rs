@@ -0,0 +1,233 @@
+use std::cmp;
+
+use crate::rangeset::RangeSet;
+
+/**
+ * A piecewise mapping from integers to integers. The domain is split into
+ * ranges, and each range is shifted by its own constant offset. Anything not
+ * covered by an explicit piece maps to itself.
+ *
+ * This is the shape of "seed-to-soil" style puzzle maps: each line says that
+ * some source range moves to a destination range. Because every piece is just
+ * a translation, whole ranges can be pushed through the map at once, maps can
+ * be composed into a single map, and (when the map is a bijection) inverted.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalMap {
+    /* Sorted, disjoint (start, end, offset) triples, inclusive on both ends,
+     * with no zero offsets and no touching pieces that share an offset. */
+    pieces: Vec<(isize, isize, isize)>,
+}
+
+impl IntervalMap {
+    /** Create a map which sends every integer to itself. */
+    pub fn identity() -> Self {
+        IntervalMap{pieces: Vec::new()}
+    }
+
+    /** Create a map from (destination start, source start, length) triples,
+     * the way they are written in the puzzle input. Where triples overlap, the
+     * first one listed wins, as with a lookup which stops at the first match. */
+    pub fn from_triples<I>(triples: I) -> Self
+    where I: IntoIterator<Item = (isize, isize, isize)> {
+        let mut map = Self::identity();
+        /* Offset 0 pieces aren't stored, so track what's been claimed. */
+        let mut claimed = RangeSet::new();
+        for (dst, src, len) in triples {
+            let (start, end) = (src, src + len - 1);
+            for gap in RangeSet::from_range(start, end).difference(&claimed).ranges() {
+                map.insert(*gap.start(), *gap.end(), dst - src);
+            }
+            claimed.add(start, end);
+        }
+        map
+    }
+
+    /**
+     * Map [start, end] -- INCLUSIVE on both ends -- by adding offset, replacing
+     * whatever mapping those values had before.
+     */
+    pub fn insert(&mut self, start: isize, end: isize, offset: isize) {
+        if start > end {
+            return;
+        }
+        let mut pieces: Vec<(isize, isize, isize)> = Vec::new();
+        for &(s, e, o) in self.pieces.iter() {
+            if e < start || s > end {
+                pieces.push((s, e, o));
+                continue;
+            }
+            if s < start {
+                pieces.push((s, start - 1, o));
+            }
+            if e > end {
+                pieces.push((end + 1, e, o));
+            }
+        }
+        pieces.push((start, end, offset));
+        self.set_pieces(pieces);
+    }
+
+    fn set_pieces(&mut self, mut pieces: Vec<(isize, isize, isize)>) {
+        pieces.sort();
+        self.pieces.clear();
+        for (s, e, o) in pieces {
+            if o == 0 {
+                continue;
+            }
+            match self.pieces.last_mut() {
+                Some(last) if last.1 + 1 == s && last.2 == o => last.1 = e,
+                _ => self.pieces.push((s, e, o)),
+            }
+        }
+    }
+
+    /** The explicit pieces, as (start, end, offset), in order. */
+    pub fn pieces(&self) -> &[(isize, isize, isize)] {
+        &self.pieces
+    }
+
+    /** Every piece including the identity gaps, covering all of isize. */
+    fn segments(&self) -> Vec<(isize, isize, isize)> {
+        let mut segs = Vec::new();
+        let mut next = isize::MIN;
+        for &(s, e, o) in self.pieces.iter() {
+            if s > next {
+                segs.push((next, s - 1, 0));
+            }
+            segs.push((s, e, o));
+            next = match e.checked_add(1) {
+                Some(n) => n,
+                None => return segs,
+            };
+        }
+        segs.push((next, isize::MAX, 0));
+        segs
+    }
+
+    /** Map a single value. */
+    pub fn apply(&self, val: isize) -> isize {
+        let i = self.pieces.partition_point(|t| t.1 < val);
+        match self.pieces.get(i) {
+            Some(&(s, _, o)) if s <= val => val + o,
+            _ => val,
+        }
+    }
+
+    /** Map every member of a set, returning the set of results. */
+    pub fn apply_set(&self, set: &RangeSet) -> RangeSet {
+        let mut res = RangeSet::new();
+        for (s, e, o) in self.segments() {
+            for range in set.get_ranges_between(s, e) {
+                res.add(range.start() + o, range.end() + o);
+            }
+        }
+        res
+    }
+
+    /** Return every value which this map sends into the set. */
+    pub fn preimage(&self, set: &RangeSet) -> RangeSet {
+        let mut res = RangeSet::new();
+        for (s, e, o) in self.segments() {
+            for range in set.get_ranges_between(s + o, e + o) {
+                res.add(range.start() - o, range.end() - o);
+            }
+        }
+        res
+    }
+
+    /**
+     * Compose two maps: the result sends x to next.apply(self.apply(x)). Folding
+     * a chain of maps with this collapses them into a single lookup.
+     */
+    pub fn then(&self, next: &Self) -> Self {
+        let next_segs = next.segments();
+        let mut pieces = Vec::new();
+        for (s, e, o) in self.segments() {
+            /* Split the image of this segment wherever next changes offset. */
+            let (img_s, img_e) = (s + o, e + o);
+            let i = next_segs.partition_point(|t| t.1 < img_s);
+            for &(s2, e2, o2) in next_segs[i..].iter().take_while(|t| t.0 <= img_e) {
+                let start = cmp::max(s2, img_s) - o;
+                let end = cmp::min(e2, img_e) - o;
+                pieces.push((start, end, o + o2));
+            }
+        }
+        let mut res = Self::identity();
+        res.set_pieces(pieces);
+        res
+    }
+
+    /**
+     * Return the inverse map, if this map is a bijection (no two values map to
+     * the same place). Use preimage() for maps which aren't.
+     */
+    pub fn inverse(&self) -> Option<Self> {
+        let mut images: Vec<(isize, isize, isize)> = self
+            .segments()
+            .into_iter()
+            .map(|(s, e, o)| (s + o, e + o, -o))
+            .collect();
+        images.sort();
+        let mut next = Some(isize::MIN);
+        for &(s, e, _) in images.iter() {
+            if next != Some(s) {
+                return None;
+            }
+            next = e.checked_add(1);
+        }
+        if next.is_some() {
+            return None;
+        }
+        let mut res = Self::identity();
+        res.set_pieces(images);
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_triples_first_wins() {
+        /* 5..=14 moves by +100, then 10..=19 would move by +200. */
+        let map = IntervalMap::from_triples([(105, 5, 10), (210, 10, 10)]);
+        assert_eq!(map.apply(4), 4);
+        assert_eq!(map.apply(12), 112);
+        assert_eq!(map.apply(15), 215);
+        assert_eq!(map.apply(20), 20);
+    }
+
+    #[test]
+    fn from_triples_identity_triple_still_claims() {
+        let map = IntervalMap::from_triples([(0, 0, 10), (100, 5, 10)]);
+        assert_eq!(map.apply(7), 7);
+        assert_eq!(map.apply(12), 107);
+    }
+
+    #[test]
+    fn compose_and_invert() {
+        let a = IntervalMap::from_triples([(50, 98, 2), (52, 50, 48)]);
+        let b = IntervalMap::from_triples([(0, 15, 37), (37, 52, 2), (39, 0, 15)]);
+        let ab = a.then(&b);
+        for x in 0..120 {
+            assert_eq!(ab.apply(x), b.apply(a.apply(x)));
+        }
+        let inv = a.inverse().unwrap();
+        for x in 0..120 {
+            assert_eq!(inv.apply(a.apply(x)), x);
+        }
+        let squash = IntervalMap::from_triples([(0, 10, 5)]);
+        assert!(squash.inverse().is_none());
+    }
+
+    #[test]
+    fn sets_and_preimages() {
+        let map = IntervalMap::from_triples([(100, 0, 10)]);
+        let img = map.apply_set(&RangeSet::from_range(5, 15));
+        assert_eq!(img.ranges().collect::<Vec<_>>(), vec![10..=15, 105..=109]);
+        let pre = map.preimage(&RangeSet::from_range(100, 102));
+        assert_eq!(pre.ranges().collect::<Vec<_>>(), vec![0..=2, 100..=102]);
+    }
+}
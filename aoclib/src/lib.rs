@@ -0,0 +1,22 @@
+//! Helpers shared between the solutions for each year.
+
+pub mod bigint;
+pub mod checked;
+pub mod combinatorics;
+pub mod cube;
+pub mod cycle;
+pub mod expr;
+pub mod graph;
+pub mod intern;
+pub mod intervalmap;
+pub mod math;
+pub mod nested;
+pub mod numeral;
+pub mod optimize;
+pub mod poly;
+pub mod rangeset;
+pub mod rational;
+pub mod search;
+pub mod tetris;
+pub mod vm;
+pub mod voxel;
@@ -0,0 +1,359 @@
+use std::error::Error;
+use std::fmt;
+
+/// Greatest common divisor, always non-negative. gcd(0, 0) is 0. None only
+/// when the answer is 2^63, which happens if both are multiples of i64::MIN.
+pub fn gcd(a: i64, b: i64) -> Option<i64> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i64::try_from(a).ok()
+}
+
+/// Least common multiple, always non-negative, or None if it overflows.
+/// lcm(0, n) is 0.
+pub fn lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)?).checked_mul(b)?.checked_abs()
+}
+
+/// The gcd of every value (0 if there are none), or None on overflow.
+pub fn gcd_all<I: IntoIterator<Item = i64>>(vals: I) -> Option<i64> {
+    vals.into_iter().try_fold(0, gcd)
+}
+
+/// The lcm of every value (1 if there are none), or None on overflow.
+pub fn lcm_all<I: IntoIterator<Item = i64>>(vals: I) -> Option<i64> {
+    vals.into_iter().try_fold(1, lcm)
+}
+
+/// Extended Euclid: returns (g, x, y) where g = gcd(a, b) >= 0 and
+/// a*x + b*y = g. None if any of them doesn't fit in an i64, which only
+/// happens when g would be 2^63, as for gcd().
+pub fn ext_gcd(a: i64, b: i64) -> Option<(i64, i64, i64)> {
+    let (mut r0, mut r1) = (a as i128, b as i128);
+    let (mut x0, mut x1) = (1i128, 0i128);
+    let (mut y0, mut y1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0.div_euclid(r1);
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 {
+        (r0, x0, y0) = (-r0, -x0, -y0);
+    }
+    Some((i64::try_from(r0).ok()?, i64::try_from(x0).ok()?, i64::try_from(y0).ok()?))
+}
+
+/// `a * b mod m`, in the range 0..m, without overflowing. Panics if m isn't
+/// positive.
+pub fn mod_mul(a: i64, b: i64, m: i64) -> i64 {
+    assert!(m > 0, "modulus must be positive, got {}", m);
+    (a as i128 * b as i128).rem_euclid(m as i128) as i64
+}
+
+/// `base^exp mod m`, in the range 0..m, by repeated squaring. Panics if m
+/// isn't positive.
+pub fn mod_pow(base: i64, mut exp: u64, m: i64) -> i64 {
+    assert!(m > 0, "modulus must be positive, got {}", m);
+    let mut base = base.rem_euclid(m);
+    let mut res = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = mod_mul(res, base, m);
+        }
+        base = mod_mul(base, base, m);
+        exp >>= 1;
+    }
+    res
+}
+
+/// The x in 0..m with a*x = 1 (mod m), if a and m are coprime. Panics if m
+/// isn't positive.
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    assert!(m > 0, "modulus must be positive, got {}", m);
+    let (g, x, _) = ext_gcd(a.rem_euclid(m), m)?;
+    if g != 1 {
+        return None;
+    }
+    Some(x.rem_euclid(m))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CrtError {
+    /// A modulus was zero or negative.
+    BadModulus(i64),
+    /// No x satisfies both x = r1 (mod m1) and x = r2 (mod m2).
+    Inconsistent{r1: i64, m1: i64, r2: i64, m2: i64},
+    /// The combined modulus doesn't fit in an i64.
+    Overflow,
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtError::BadModulus(m) => write!(f, "modulus must be positive, got {}", m),
+            CrtError::Inconsistent{r1, m1, r2, m2} => write!(
+                f, "x = {} (mod {}) and x = {} (mod {}) have no common solution", r1, m1, r2, m2),
+            CrtError::Overflow => write!(f, "combined modulus overflows"),
+        }
+    }
+}
+
+impl Error for CrtError {}
+
+/// Chinese remainder theorem, generalized to moduli which needn't be coprime.
+/// Given congruences x = r (mod m), returns (x, M) where M is the lcm of the
+/// moduli and x in 0..M is the unique solution modulo M. With no congruences
+/// the answer is (0, 1): anything goes.
+pub fn crt<I: IntoIterator<Item = (i64, i64)>>(congruences: I) -> Result<(i64, i64), CrtError> {
+    let (mut x, mut m) = (0i128, 1i128);
+    for (r2, m2) in congruences {
+        if m2 <= 0 {
+            return Err(CrtError::BadModulus(m2));
+        }
+        let (r2, m2) = ((r2 as i128).rem_euclid(m2 as i128), m2 as i128);
+        /* Solve x + m*k = r2 (mod m2), i.e. m*k = r2 - x (mod m2). */
+        /* Both moduli are positive, so their gcd fits. */
+        let (g, p, _) = ext_gcd(m as i64, m2 as i64).ok_or(CrtError::Overflow)?;
+        let g = g as i128;
+        let diff = r2 - x;
+        if diff % g != 0 {
+            return Err(CrtError::Inconsistent{r1: x as i64, m1: m as i64, r2: r2 as i64, m2: m2 as i64});
+        }
+        let step = m2 / g;
+        let k = ((diff / g) % step * (p as i128 % step)).rem_euclid(step);
+        let lcm = m * step;
+        if lcm > i64::MAX as i128 {
+            return Err(CrtError::Overflow);
+        }
+        x = (x + m * k).rem_euclid(lcm);
+        m = lcm;
+    }
+    Ok((x as i64, m as i64))
+}
+
+/// Floor of the square root. Panics if n is negative.
+pub fn isqrt(n: i128) -> i128 {
+    assert!(n >= 0, "square root of negative number {}", n);
+    if n < 2 {
+        return n;
+    }
+    /* Newton's method from above, starting from a power of two which is
+     * definitely big enough. */
+    let mut x: i128 = 1 << ((128 - n.leading_zeros()).div_ceil(2));
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/*
+ * For a quadratic with a > 0, the values of x where it's negative (or zero)
+ * lie between the two roots, (-b ± sqrt(b^2 - 4ac)) / 2a. Taking the integer
+ * square root gets us within one of the right answer at each end, and then
+ * evaluating the polynomial exactly tells us which side of the boundary we're
+ * on, which matters most when a root is itself an integer: that's the
+ * difference between < and <=.
+ */
+fn quadratic_range<F>(a: i64, b: i64, c: i64, ok: F) -> Option<(i64, i64)>
+where F: Fn(i128) -> bool {
+    assert!(a > 0, "quadratic must open upward (a > 0), got a = {}", a);
+    let (a, b, c) = (a as i128, b as i128, c as i128);
+    let disc = b * b - 4 * a * c;
+    if disc < 0 {
+        return None;
+    }
+    let f = |x: i128| ok(a * x * x + b * x + c);
+    let s = isqrt(disc);
+    let mut lo = (-b - s).div_euclid(2 * a);
+    let mut hi = (-b + s).div_euclid(2 * a) + 1;
+    while f(lo - 1) {
+        lo -= 1;
+    }
+    while lo <= hi && !f(lo) {
+        lo += 1;
+    }
+    while f(hi + 1) {
+        hi += 1;
+    }
+    while hi >= lo && !f(hi) {
+        hi -= 1;
+    }
+    if lo > hi {
+        return None;
+    }
+    Some((i64::try_from(lo).ok()?, i64::try_from(hi).ok()?))
+}
+
+/// The integers x where a*x^2 + b*x + c < 0, as an inclusive range (so there
+/// are hi - lo + 1 of them), or None if there aren't any. Requires a > 0.
+pub fn quadratic_below(a: i64, b: i64, c: i64) -> Option<(i64, i64)> {
+    quadratic_range(a, b, c, |v| v < 0)
+}
+
+/// Like `quadratic_below()`, but for a*x^2 + b*x + c <= 0.
+pub fn quadratic_at_most(a: i64, b: i64, c: i64) -> Option<(i64, i64)> {
+    quadratic_range(a, b, c, |v| v <= 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(12, -18), Some(6));
+        assert_eq!(gcd(0, 0), Some(0));
+        assert_eq!(gcd(-7, 0), Some(7));
+        assert_eq!(lcm(4, -6), Some(12));
+        assert_eq!(lcm(0, 5), Some(0));
+        assert_eq!(gcd_all([12, 18, 30]), Some(6));
+        assert_eq!(lcm_all([2, 3, 4]), Some(12));
+        assert_eq!(lcm_all([]), Some(1));
+    }
+
+    #[test]
+    fn gcd_and_lcm_at_the_edges() {
+        assert_eq!(gcd(i64::MIN, 0), None);
+        assert_eq!(gcd(i64::MIN, i64::MIN), None);
+        assert_eq!(gcd(i64::MIN, 6), Some(2));
+        assert_eq!(gcd(i64::MIN, i64::MAX), Some(1));
+        assert_eq!(lcm(i64::MIN, 1), None);
+        assert_eq!(lcm(i64::MIN, -1), None);
+        assert_eq!(lcm(i64::MAX, -1), Some(i64::MAX));
+        assert_eq!(lcm(i64::MAX, 2), None);
+        assert_eq!(gcd_all([i64::MIN, 0]), None);
+    }
+
+    #[test]
+    fn isqrt_is_the_floor() {
+        for n in 0..10_000i128 {
+            let s = isqrt(n);
+            assert!(s * s <= n && (s + 1) * (s + 1) > n, "isqrt({}) = {}", n, s);
+        }
+        let big = (i64::MAX as i128) * (i64::MAX as i128);
+        assert_eq!(isqrt(big), i64::MAX as i128);
+        assert_eq!(isqrt(big - 1), i64::MAX as i128 - 1);
+    }
+
+    /// The range of x in -200..=200 where ok(a*x^2 + b*x + c), which is all of
+    /// them as long as the roots are well inside.
+    fn brute_force<F: Fn(i64) -> bool>(a: i64, b: i64, c: i64, ok: F) -> Option<(i64, i64)> {
+        let xs: Vec<i64> = (-200..=200).filter(|x| ok(a * x * x + b * x + c)).collect();
+        if let (Some(lo), Some(hi)) = (xs.first(), xs.last()) {
+            assert_eq!((hi - lo + 1) as usize, xs.len());
+            Some((*lo, *hi))
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn quadratic_ranges_match_brute_force() {
+        for a in 1..=4 {
+            for b in -30..=30 {
+                for c in -60..=60 {
+                    assert_eq!(quadratic_below(a, b, c), brute_force(a, b, c, |v| v < 0),
+                               "{}x^2 + {}x + {} < 0", a, b, c);
+                    assert_eq!(quadratic_at_most(a, b, c), brute_force(a, b, c, |v| v <= 0),
+                               "{}x^2 + {}x + {} <= 0", a, b, c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quadratic_edge_cases() {
+        /* Integer roots: 10 and 20 themselves only count for <=. */
+        assert_eq!(quadratic_below(1, -30, 200), Some((11, 19)));
+        assert_eq!(quadratic_at_most(1, -30, 200), Some((10, 20)));
+        /* A root that only touches zero, (x - 3)^2. */
+        assert_eq!(quadratic_below(1, -6, 9), None);
+        assert_eq!(quadratic_at_most(1, -6, 9), Some((3, 3)));
+        /* Real roots (0.4 and 0.6) with no integer between them. */
+        assert_eq!(quadratic_below(100, -100, 24), None);
+        assert_eq!(quadratic_at_most(100, -100, 24), None);
+        /* No real roots at all. */
+        assert_eq!(quadratic_below(1, 0, 1), None);
+        assert_eq!(quadratic_at_most(1, 0, 1), None);
+        /* Roots too big for floating point to tell apart from their
+         * neighbours: (x - 1)(x - (t - 1)). */
+        let t = 3_000_000_000i64;
+        assert_eq!(quadratic_below(1, -t, t - 1), Some((2, t - 2)));
+        assert_eq!(quadratic_at_most(1, -t, t - 1), Some((1, t - 1)));
+    }
+
+    #[test]
+    fn ext_gcd_identity() {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut pairs = vec![(0, 0), (0, 5), (-5, 0), (12, -18), (i64::MAX, i64::MAX - 1), (i64::MIN, 3), (i64::MIN + 1, i64::MAX)];
+        for _ in 0..1000 {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            pairs.push(((seed >> 1) as i64 - (1 << 62), (seed % 1000) as i64 - 500));
+        }
+        for (a, b) in pairs {
+            let (g, x, y) = ext_gcd(a, b).unwrap();
+            assert_eq!(Some(g), gcd(a, b), "ext_gcd({}, {})", a, b);
+            assert_eq!(a as i128 * x as i128 + b as i128 * y as i128, g as i128, "ext_gcd({}, {})", a, b);
+        }
+    }
+
+    #[test]
+    fn ext_gcd_overflow() {
+        assert_eq!(ext_gcd(i64::MIN, 0), None);
+        assert_eq!(ext_gcd(0, i64::MIN), None);
+        assert_eq!(ext_gcd(i64::MIN, i64::MIN), None);
+        assert_eq!(ext_gcd(i64::MIN, 2).map(|r| r.0), Some(2));
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        assert_eq!(mod_mul(-3, 5, 7), 6);
+        assert_eq!(mod_mul(i64::MAX, i64::MAX, 1_000_000_007), ((i64::MAX as i128).pow(2) % 1_000_000_007) as i64);
+        assert_eq!(mod_pow(2, 10, 1000), 24);
+        assert_eq!(mod_pow(-2, 3, 5), 2);
+        assert_eq!(mod_pow(5, 0, 1), 0);
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(-3, 7), Some(2));
+        assert_eq!(mod_inverse(4, 8), None);
+        assert_eq!(mod_inverse(5, 1), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus must be positive, got 0")]
+    fn mod_mul_zero_modulus() {
+        mod_mul(2, 3, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus must be positive, got -7")]
+    fn mod_pow_negative_modulus() {
+        mod_pow(2, 3, -7);
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus must be positive, got 0")]
+    fn mod_inverse_zero_modulus() {
+        mod_inverse(2, 0);
+    }
+
+    #[test]
+    fn crt_cases() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        assert_eq!(crt([(1, 4), (3, 6)]), Ok((9, 12)));
+        assert_eq!(crt([]), Ok((0, 1)));
+        assert!(matches!(crt([(1, 4), (2, 6)]), Err(CrtError::Inconsistent{..})));
+        assert_eq!(crt([(1, 0)]), Err(CrtError::BadModulus(0)));
+        assert_eq!(crt([(0, i64::MAX), (0, i64::MAX - 1)]), Err(CrtError::Overflow));
+    }
+}
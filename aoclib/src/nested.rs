@@ -0,0 +1,298 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A JSON-like nested value: integers, strings, and lists of either. The text
+/// form is `[1,[2,"three"],[]]`, which is what Display produces and FromStr
+/// accepts (with optional whitespace between tokens).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Nested {
+    Int(i64),
+    Str(String),
+    List(Vec<Nested>),
+}
+
+/// How two nested values are ordered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// Order by kind first (integer < string < list), then by contents, with
+    /// lists compared element by element.
+    Structural,
+    /// Like Structural, except that when a list is compared against a scalar,
+    /// the scalar is first wrapped in a one-element list (2022 day 13).
+    Promote,
+}
+
+impl Nested {
+    fn kind(&self) -> u8 {
+        match self {
+            Nested::Int(_) => 0,
+            Nested::Str(_) => 1,
+            Nested::List(_) => 2,
+        }
+    }
+
+    pub fn compare(&self, other: &Self, how: Comparison) -> Ordering {
+        use Nested::*;
+        match (self, other) {
+            (Int(l), Int(r)) => l.cmp(r),
+            (Str(l), Str(r)) => l.cmp(r),
+            (List(l), List(r)) => {
+                for (ll, rr) in l.iter().zip(r.iter()) {
+                    let sub = ll.compare(rr, how);
+                    if sub != Ordering::Equal {
+                        return sub;
+                    }
+                }
+                l.len().cmp(&r.len())
+            }
+            (List(_), r) if how == Comparison::Promote => {
+                self.compare(&List(vec![r.clone()]), how)
+            }
+            (l, List(_)) if how == Comparison::Promote => {
+                List(vec![l.clone()]).compare(other, how)
+            }
+            (l, r) => l.kind().cmp(&r.kind()),
+        }
+    }
+}
+
+impl PartialOrd for Nested {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The natural ordering is structural, which is consistent with equality.
+impl Ord for Nested {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other, Comparison::Structural)
+    }
+}
+
+impl fmt::Display for Nested {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Nested::Int(v) => write!(f, "{}", v),
+            Nested::Str(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' | '\\' => write!(f, "\\{}", c)?,
+                        _ => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Nested::List(l) => {
+                write!(f, "[")?;
+                for (i, item) in l.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// A parse failure, with the byte offset in the input where it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub pos: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at position {}: {}", self.pos, self.msg)
+    }
+}
+
+impl Error for ParseError {}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn err<T>(&self, msg: &str) -> Result<T, ParseError> {
+        Err(ParseError{pos: self.pos, msg: msg.to_string()})
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Nested, ParseError> {
+        self.skip_space();
+        match self.peek() {
+            None => self.err("expected a value, found end of input"),
+            Some(b'[') => self.list(),
+            Some(b'"') => self.string(),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.int(),
+            Some(c) => self.err(&format!("unexpected character {:?}", c as char)),
+        }
+    }
+
+    fn list(&mut self) -> Result<Nested, ParseError> {
+        self.pos += 1; /* skip '[' */
+        let mut l = Vec::new();
+        self.skip_space();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Nested::List(l));
+        }
+        loop {
+            l.push(self.value()?);
+            self.skip_space();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Nested::List(l));
+                }
+                None => return self.err("unterminated list"),
+                Some(c) => return self.err(&format!("expected ',' or ']', found {:?}", c as char)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<Nested, ParseError> {
+        let start = self.pos;
+        self.pos += 1; /* skip '"' */
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => {
+                    self.pos = start;
+                    return self.err("unterminated string");
+                }
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c @ (b'"' | b'\\')) => bytes.push(c),
+                        _ => return self.err("invalid escape sequence"),
+                    }
+                }
+                Some(c) => bytes.push(c),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(Nested::Str(s)),
+            Err(_) => {
+                self.pos = start;
+                self.err("string is not valid UTF-8")
+            }
+        }
+    }
+
+    fn int(&mut self) -> Result<Nested, ParseError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.s[start..self.pos]).unwrap();
+        match text.parse() {
+            Ok(v) => Ok(Nested::Int(v)),
+            Err(e) => {
+                self.pos = start;
+                self.err(&format!("invalid integer {:?}: {}", text, e))
+            }
+        }
+    }
+}
+
+impl FromStr for Nested {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser{s: s.as_bytes(), pos: 0};
+        let val = p.value()?;
+        p.skip_space();
+        if p.pos != s.len() {
+            return p.err("trailing characters after value");
+        }
+        Ok(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_round_trips() {
+        let cases = [
+            "0",
+            "-17",
+            "[]",
+            "[[]]",
+            "[1,[2,\"three\"],[]]",
+            "[[1],[2,3,4]]",
+            "[\"\",\"say \\\"hi\\\"\",\"back\\\\slash\",\"ünïcode\"]",
+            "[[[[[[-9223372036854775808]]]]],9223372036854775807]",
+        ];
+        for s in cases {
+            let v: Nested = s.parse().unwrap();
+            assert_eq!(v.to_string(), s);
+            assert_eq!(v.to_string().parse::<Nested>(), Ok(v));
+        }
+        let spaced: Nested = " [ 1 ,\t[ 2 ] , \"a b\" ]\n".parse().unwrap();
+        assert_eq!(spaced.to_string(), "[1,[2],\"a b\"]");
+    }
+
+    fn err(s: &str) -> (usize, String) {
+        let e = s.parse::<Nested>().unwrap_err();
+        (e.pos, e.msg)
+    }
+
+    #[test]
+    fn error_positions() {
+        /* Truncated input */
+        assert_eq!(err(""), (0, "expected a value, found end of input".to_string()));
+        assert_eq!(err("[1,"), (3, "expected a value, found end of input".to_string()));
+        assert_eq!(err("[1,[2"), (5, "unterminated list".to_string()));
+        assert_eq!(err("[\"abc"), (1, "unterminated string".to_string()));
+        /* Stray characters */
+        assert_eq!(err("[1,x]"), (3, "unexpected character 'x'".to_string()));
+        assert_eq!(err("[1 2]"), (3, "expected ',' or ']', found '2'".to_string()));
+        assert_eq!(err("[1] x"), (4, "trailing characters after value".to_string()));
+        assert_eq!(err("[\"a\\n\"]"), (4, "invalid escape sequence".to_string()));
+        /* Bad numbers */
+        assert_eq!(err("[1,-]").0, 3);
+        assert!(err("[1,-]").1.starts_with("invalid integer \"-\""));
+        assert_eq!(err("[0,99999999999999999999]").0, 3);
+        assert!(err("[0,99999999999999999999]").1.contains("too large"));
+        assert_eq!(ParseError{pos: 3, msg: "oops".into()}.to_string(), "at position 3: oops");
+    }
+
+    #[test]
+    fn comparisons() {
+        let p = |s: &str| s.parse::<Nested>().unwrap();
+        assert_eq!(p("[1,1,3,1,1]").compare(&p("[1,1,5,1,1]"), Comparison::Promote), Ordering::Less);
+        assert_eq!(p("[[1],[2,3,4]]").compare(&p("[[1],4]"), Comparison::Promote), Ordering::Less);
+        assert_eq!(p("[9]").compare(&p("[[8,7,6]]"), Comparison::Promote), Ordering::Greater);
+        assert_eq!(p("[[[]]]").compare(&p("[[]]"), Comparison::Promote), Ordering::Greater);
+        assert_eq!(p("[3]").compare(&p("3"), Comparison::Promote), Ordering::Equal);
+        /* Structurally, kinds order before contents. */
+        assert!(p("3") < p("\"a\"") && p("\"a\"") < p("[]"));
+        assert!(p("[3]") > p("3"));
+        assert!(p("[1,2]") < p("[1,2,0]") && p("[1,3]") > p("[1,2,0]"));
+    }
+}
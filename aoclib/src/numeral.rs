@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::bigint::BigInt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NumeralError {
+    /// Fewer than two symbols.
+    BadBase(usize),
+    /// The same symbol appears twice in the table.
+    DuplicateSymbol(char),
+    /// No symbol has the value zero, so not every number can be written.
+    NoZero,
+    /// A character which isn't in the symbol table, at this position.
+    BadDigit{digit: char, pos: usize},
+    /// An empty string isn't a number.
+    Empty,
+    /// The digits can't express a number with this sign.
+    Unrepresentable(String),
+}
+
+impl fmt::Display for NumeralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumeralError::BadBase(n) => write!(f, "need at least two symbols, got {}", n),
+            NumeralError::DuplicateSymbol(c) => write!(f, "symbol {:?} appears more than once", c),
+            NumeralError::NoZero => write!(f, "no symbol has the value zero"),
+            NumeralError::BadDigit{digit, pos} => write!(f, "bad digit {:?} at position {}", digit, pos),
+            NumeralError::Empty => write!(f, "empty numeral"),
+            NumeralError::Unrepresentable(v) => write!(f, "{} can't be written with these digits", v),
+        }
+    }
+}
+
+impl Error for NumeralError {}
+
+/// A positional numeral system. The base is the number of symbols, and the
+/// symbols stand for consecutive values starting at `low`: low = 0 gives the
+/// usual digits, and a negative low gives a balanced system like SNAFU, where
+/// "=-012" are -2 to 2 in base 5. Numerals are written most significant digit
+/// first, with no sign.
+#[derive(Clone, Debug)]
+pub struct Numeral {
+    symbols: Vec<char>,
+    values: HashMap<char, i32>,
+    low: i32,
+}
+
+impl Numeral {
+    pub fn new(symbols: &str, low: i32) -> Result<Numeral, NumeralError> {
+        let symbols: Vec<char> = symbols.chars().collect();
+        if symbols.len() < 2 {
+            return Err(NumeralError::BadBase(symbols.len()));
+        }
+        if low > 0 || low + symbols.len() as i32 <= 0 {
+            return Err(NumeralError::NoZero);
+        }
+        let mut values = HashMap::new();
+        for (i, c) in symbols.iter().enumerate() {
+            if values.insert(*c, low + i as i32).is_some() {
+                return Err(NumeralError::DuplicateSymbol(*c));
+            }
+        }
+        Ok(Numeral{symbols, values, low})
+    }
+
+    /// Digits 0 to base - 1, e.g. "0123456789abcdef".
+    pub fn standard(symbols: &str) -> Result<Numeral, NumeralError> {
+        Numeral::new(symbols, 0)
+    }
+
+    /// Digits centred on zero, e.g. "-0+" for balanced ternary. An even base
+    /// has one more negative digit than positive.
+    pub fn balanced(symbols: &str) -> Result<Numeral, NumeralError> {
+        Numeral::new(symbols, -(symbols.chars().count() as i32 / 2))
+    }
+
+    /// The SNAFU numbers from 2022 day 25.
+    pub fn snafu() -> Numeral {
+        Numeral::balanced("=-012").unwrap()
+    }
+
+    pub fn base(&self) -> u32 {
+        self.symbols.len() as u32
+    }
+
+    fn high(&self) -> i32 {
+        self.low + self.symbols.len() as i32 - 1
+    }
+
+    fn symbol(&self, digit: i32) -> char {
+        self.symbols[(digit - self.low) as usize]
+    }
+
+    /// Digit values, least significant first.
+    fn digits(&self, s: &str) -> Result<Vec<i32>, NumeralError> {
+        if s.is_empty() {
+            return Err(NumeralError::Empty);
+        }
+        let mut digits = s.chars().enumerate().map(|(pos, digit)| {
+            self.values.get(&digit).copied().ok_or(NumeralError::BadDigit{digit, pos})
+        }).collect::<Result<Vec<_>, _>>()?;
+        digits.reverse();
+        Ok(digits)
+    }
+
+    /// Write out digits given least significant first, dropping leading zeros.
+    fn render(&self, digits: &[i32]) -> String {
+        match digits.iter().rposition(|d| *d != 0) {
+            Some(top) => digits[..=top].iter().rev().map(|d| self.symbol(*d)).collect(),
+            None => self.symbol(0).to_string(),
+        }
+    }
+
+    /// Split `v` into a digit in low..=high and what's left for the higher
+    /// places, so that v = digit + base * carry.
+    fn split(&self, v: i64) -> (i32, i64) {
+        let base = self.base() as i64;
+        let digit = (v - self.low as i64).rem_euclid(base) + self.low as i64;
+        (digit as i32, (v - digit) / base)
+    }
+
+    pub fn decode(&self, s: &str) -> Result<BigInt, NumeralError> {
+        let base = BigInt::from(self.base());
+        let mut val = BigInt::zero();
+        for digit in self.digits(s)?.iter().rev() {
+            val = &val * &base + BigInt::from(*digit);
+        }
+        Ok(val)
+    }
+
+    pub fn encode(&self, v: &BigInt) -> Result<String, NumeralError> {
+        /* Without a negative digit there's no way to get below zero, and vice
+         * versa; otherwise the digits always catch up with the value. */
+        if (v.is_negative() && self.low == 0) || (v.is_positive() && self.high() == 0) {
+            return Err(NumeralError::Unrepresentable(v.to_string()));
+        }
+        let base = BigInt::from(self.base());
+        let mut digits = Vec::new();
+        let mut v = v.clone();
+        while !v.is_zero() {
+            let (_, rem) = v.div_rem(&base);
+            let rem = i64::try_from(&rem).unwrap();
+            let (digit, carry) = self.split(rem);
+            digits.push(digit);
+            v = &v / &base + BigInt::from(carry);
+        }
+        Ok(self.render(&digits))
+    }
+
+    /// Add two numerals digit by digit, without converting them to numbers.
+    pub fn add(&self, a: &str, b: &str) -> Result<String, NumeralError> {
+        let (a, b) = (self.digits(a)?, self.digits(b)?);
+        let mut digits = Vec::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0i64;
+        for i in 0..a.len().max(b.len()) {
+            let sum = *a.get(i).unwrap_or(&0) as i64 + *b.get(i).unwrap_or(&0) as i64 + carry;
+            let (digit, next) = self.split(sum);
+            digits.push(digit);
+            carry = next;
+        }
+        while carry != 0 {
+            let (digit, next) = self.split(carry);
+            digits.push(digit);
+            carry = next;
+        }
+        Ok(self.render(&digits))
+    }
+
+    /// Add up any number of numerals; the sum of none is zero.
+    pub fn sum<'a, I>(&self, numerals: I) -> Result<String, NumeralError>
+    where I: IntoIterator<Item = &'a str> {
+        numerals.into_iter().try_fold(self.symbol(0).to_string(), |acc, n| self.add(&acc, n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn systems() -> Vec<Numeral> {
+        vec![
+            Numeral::standard("01").unwrap(),
+            Numeral::standard("0123456789").unwrap(),
+            Numeral::standard("0123456789abcdef").unwrap(),
+            Numeral::balanced("-0+").unwrap(),
+            Numeral::balanced("=-01").unwrap(),
+            Numeral::snafu(),
+        ]
+    }
+
+    #[test]
+    fn round_trips() {
+        for n in systems() {
+            for v in -2000i64..=2000 {
+                let big = BigInt::from(v);
+                match n.encode(&big) {
+                    Ok(s) => {
+                        assert_eq!(n.decode(&s), Ok(big), "{:?} in base {}", s, n.base());
+                        assert_eq!(n.encode(&n.decode(&s).unwrap()).as_ref(), Ok(&s));
+                    }
+                    Err(e) => {
+                        assert!(v < 0 && n.low == 0, "can't encode {} in base {}: {}", v, n.base(), e);
+                        assert_eq!(e, NumeralError::Unrepresentable(v.to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_known_values() {
+        let hex = Numeral::standard("0123456789abcdef").unwrap();
+        let snafu = Numeral::snafu();
+        for v in [0u64, 1, 15, 16, 255, 4096, u64::MAX] {
+            assert_eq!(hex.encode(&BigInt::from(v)).unwrap(), format!("{:x}", v));
+        }
+        for (v, s) in [(0, "0"), (3, "1="), (8, "2="), (2022, "1=11-2"), (12345, "1-0---0"), (314159265, "1121-1110-1=0")] {
+            assert_eq!(snafu.encode(&BigInt::from(v)).unwrap(), s);
+            assert_eq!(snafu.decode(s), Ok(BigInt::from(v)));
+        }
+        let big: BigInt = "123456789012345678901234567890".parse().unwrap();
+        assert_eq!(hex.decode(&hex.encode(&big).unwrap()), Ok(big.clone()));
+        assert_eq!(snafu.decode(&snafu.encode(&-&big).unwrap()), Ok(-&big));
+        assert_eq!(hex.decode("007"), Ok(BigInt::from(7)));
+    }
+
+    #[test]
+    fn add_and_sum_agree_with_decoding() {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        for n in systems() {
+            let mut encoded: Vec<String> = Vec::new();
+            let mut total = BigInt::zero();
+            for _ in 0..200 {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                let mut v = BigInt::from((seed % 100_000) as i64 - 50_000);
+                if n.low == 0 && v.is_negative() {
+                    v = -v;
+                }
+                let s = n.encode(&v).unwrap();
+                if let Some(prev) = encoded.last() {
+                    let pair = n.decode(prev).unwrap() + &v;
+                    assert_eq!(n.add(prev, &s), n.encode(&pair));
+                }
+                total += &v;
+                encoded.push(s);
+            }
+            assert_eq!(n.sum(encoded.iter().map(|s| s.as_str())), n.encode(&total));
+            assert_eq!(n.sum(std::iter::empty()), n.encode(&BigInt::zero()));
+        }
+    }
+
+    #[test]
+    fn one_sided_digits() {
+        let negative = Numeral::new("abc", -2).unwrap();
+        assert_eq!(negative.decode("ab"), Ok(BigInt::from(-7)));
+        assert_eq!(negative.encode(&BigInt::from(-7)).unwrap(), "ab");
+        assert_eq!(negative.encode(&BigInt::from(1)), Err(NumeralError::Unrepresentable("1".to_string())));
+        let binary = Numeral::standard("01").unwrap();
+        assert_eq!(binary.encode(&BigInt::from(-1)), Err(NumeralError::Unrepresentable("-1".to_string())));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Numeral::standard("0").unwrap_err(), NumeralError::BadBase(1));
+        assert_eq!(Numeral::standard("010").unwrap_err(), NumeralError::DuplicateSymbol('0'));
+        assert_eq!(Numeral::new("ab", 1).unwrap_err(), NumeralError::NoZero);
+        assert_eq!(Numeral::new("ab", -2).unwrap_err(), NumeralError::NoZero);
+        let snafu = Numeral::snafu();
+        assert_eq!(snafu.decode(""), Err(NumeralError::Empty));
+        assert_eq!(snafu.decode("12x"), Err(NumeralError::BadDigit{digit: 'x', pos: 2}));
+        assert_eq!(snafu.add("1", "3"), Err(NumeralError::BadDigit{digit: '3', pos: 0}));
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// A maximization problem for branch-and-bound search. Every state has a
+/// score, which is what you'd get if you stopped there, and an upper bound on
+/// the score of anything reachable from it. The tighter the bound, the more of
+/// the search tree gets pruned.
+pub trait Problem {
+    type State: Clone;
+    type Key: Eq + Hash;
+
+    fn successors(&self, state: &Self::State) -> Vec<Self::State>;
+
+    fn score(&self, state: &Self::State) -> i64;
+
+    /// Must never be less than the score of any state reachable from here.
+    fn upper_bound(&self, state: &Self::State) -> i64;
+
+    /// States with the same key must have the same future: whatever happens
+    /// next adds the same amount to either one's score. Then only the one with
+    /// the better score so far needs exploring. None (the default) disables
+    /// this for the state.
+    fn key(&self, _state: &Self::State) -> Option<Self::Key> {
+        None
+    }
+}
+
+/// Counts of what the search did, to see how well the bound is working.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States taken off the stack.
+    pub explored: usize,
+    /// States whose upper bound couldn't beat the best score found so far.
+    pub pruned: usize,
+    /// States skipped because one with the same key did at least as well.
+    pub memo_hits: usize,
+}
+
+impl std::ops::AddAssign for Stats {
+    fn add_assign(&mut self, rhs: Stats) {
+        self.explored += rhs.explored;
+        self.pruned += rhs.pruned;
+        self.memo_hits += rhs.memo_hits;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Solution<S> {
+    pub best: S,
+    pub score: i64,
+    pub stats: Stats,
+}
+
+/// Depth-first branch and bound from `root`. Successors are explored in the
+/// order they're returned, so put the most promising ones first to find good
+/// scores (and start pruning) early.
+pub fn maximize<P: Problem>(problem: &P, root: P::State) -> Solution<P::State> {
+    let mut stats = Stats::default();
+    let mut memo: HashMap<P::Key, i64> = HashMap::new();
+    let mut score = problem.score(&root);
+    let mut best = root.clone();
+    let mut stack = vec![root];
+    while let Some(state) = stack.pop() {
+        stats.explored += 1;
+        let cur = problem.score(&state);
+        if cur > score {
+            score = cur;
+            best = state.clone();
+        }
+        if problem.upper_bound(&state) <= score {
+            stats.pruned += 1;
+            continue;
+        }
+        if let Some(key) = problem.key(&state) {
+            match memo.get(&key) {
+                Some(prev) if *prev >= cur => {
+                    stats.memo_hits += 1;
+                    continue;
+                }
+                _ => { memo.insert(key, cur); }
+            }
+        }
+        let mut next = problem.successors(&state);
+        next.reverse();
+        stack.extend(next);
+    }
+    Solution{best, score, stats}
+}
+
+/// Solve several independent problems, spread across up to `threads` threads
+/// (each one takes the next unsolved problem until none remain). The results
+/// are in the same order as the input.
+pub fn maximize_all<P>(problems: &[(P, P::State)], threads: usize) -> Vec<Solution<P::State>>
+where P: Problem + Sync,
+      P::State: Send + Sync {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Solution<P::State>>>> = Mutex::new(vec![None; problems.len()]);
+    thread::scope(|s| {
+        for _ in 0..threads.clamp(1, problems.len().max(1)) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some((problem, root)) = problems.get(i) else { break };
+                let sol = maximize(problem, root.clone());
+                results.lock().unwrap()[i] = Some(sol);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(Option::unwrap).collect()
+}
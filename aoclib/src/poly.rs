@@ -0,0 +1,534 @@
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::bigint::BigInt;
+use crate::rational::Rational;
+
+fn lcm(a: &BigInt, b: &BigInt) -> BigInt {
+    a * b / a.gcd(b)
+}
+
+/// A polynomial in one variable X with exact rational coefficients. The
+/// coefficient at index i belongs to X^i, and there are never trailing zeros,
+/// so the zero polynomial has no coefficients at all.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Polynomial {
+    coeffs: Vec<Rational>,
+}
+
+impl Polynomial {
+    pub fn from_coeffs(mut coeffs: Vec<Rational>) -> Self {
+        while coeffs.last().is_some_and(|c| c.is_zero()) {
+            coeffs.pop();
+        }
+        Polynomial{coeffs}
+    }
+
+    pub fn zero() -> Self {
+        Polynomial{coeffs: Vec::new()}
+    }
+
+    pub fn constant(v: Rational) -> Self {
+        Polynomial::from_coeffs(vec![v])
+    }
+
+    /// The polynomial "X".
+    pub fn x() -> Self {
+        Polynomial::from_coeffs(vec![Rational::zero(), Rational::one()])
+    }
+
+    pub fn coeffs(&self) -> &[Rational] {
+        &self.coeffs
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// The highest power of X, or None for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn leading(&self) -> Rational {
+        self.coeffs.last().cloned().unwrap_or_else(Rational::zero)
+    }
+
+    pub fn eval(&self, x: &Rational) -> Rational {
+        /* Horner's method */
+        self.coeffs.iter().rev().fold(Rational::zero(), |acc, c| &acc * x + c)
+    }
+
+    pub fn scale(&self, by: &Rational) -> Self {
+        Polynomial::from_coeffs(self.coeffs.iter().map(|c| c * by).collect())
+    }
+
+    /// Polynomial long division, returning (quotient, remainder).
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        let rhs_deg = rhs.degree().expect("polynomial division by zero");
+        let lead = rhs.leading();
+        let mut rem = self.coeffs.clone();
+        let mut quot = vec![Rational::zero(); self.coeffs.len().saturating_sub(rhs_deg)];
+        while rem.len() > rhs_deg && !rem.is_empty() {
+            let shift = rem.len() - 1 - rhs_deg;
+            let factor = rem.last().unwrap() / &lead;
+            for (i, c) in rhs.coeffs.iter().enumerate() {
+                rem[shift + i] = &rem[shift + i] - &(c * &factor);
+            }
+            quot[shift] = factor;
+            rem.pop(); /* leading term is now exactly zero */
+        }
+        (Polynomial::from_coeffs(quot), Polynomial::from_coeffs(rem))
+    }
+
+    /// Greatest common divisor, scaled so its leading coefficient is 1.
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let r = a.div_rem(&b).1;
+            a = b;
+            b = r;
+        }
+        if a.is_zero() {
+            return a;
+        }
+        let lead = a.leading();
+        a.scale(&lead.recip())
+    }
+
+    /// The least common multiple of every coefficient's denominator. Scaling
+    /// by this gives a polynomial with integer coefficients.
+    fn denominator_lcm(&self) -> BigInt {
+        self.coeffs.iter().fold(BigInt::one(), |l, c| lcm(&l, c.denom()))
+    }
+
+    /// Find the rational roots of the polynomial, in increasing order and
+    /// without repeats. Irrational and complex roots are not returned.
+    pub fn roots(&self) -> Result<Vec<Rational>, String> {
+        if self.is_zero() {
+            return Err("every value is a root of the zero polynomial".into());
+        }
+        let mut roots = Vec::new();
+        /* Take out any factors of X first, so the constant term isn't zero. */
+        let zeros = self.coeffs.iter().take_while(|c| c.is_zero()).count();
+        if zeros > 0 {
+            roots.push(Rational::zero());
+        }
+        let mut rest = Polynomial::from_coeffs(self.coeffs[zeros..].to_vec());
+        while rest.degree() > Some(2) {
+            match rest.rational_root() {
+                Some(r) => {
+                    rest = rest.div_rem(&Polynomial::from_coeffs(vec![-&r, Rational::one()])).0;
+                    roots.push(r);
+                }
+                None => break,
+            }
+        }
+        if rest.degree() <= Some(2) {
+            roots.extend(rest.small_roots());
+        }
+        roots.sort();
+        roots.dedup();
+        Ok(roots)
+    }
+
+    /*
+     * By the rational root theorem, once the coefficients are integers any
+     * rational root is p/q in lowest terms where p divides the constant term
+     * and q divides the leading coefficient. So there are only finitely many
+     * candidates to try, though finding them takes time proportional to the
+     * square roots of those two coefficients.
+     */
+    fn rational_root(&self) -> Option<Rational> {
+        let scaled = self.scale(&Rational::from(self.denominator_lcm()));
+        let (first, last) = (scaled.coeffs[0].numer().abs(), scaled.leading().numer().abs());
+        let ps = divisors(&first);
+        for q in divisors(&last) {
+            for p in ps.iter() {
+                for p in [p.clone(), -p] {
+                    let r = Rational::new(p, q.clone());
+                    if self.eval(&r).is_zero() {
+                        return Some(r);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Roots of a polynomial of degree at most 2, worked out directly.
+    fn small_roots(&self) -> Vec<Rational> {
+        let scaled = self.scale(&Rational::from(self.denominator_lcm()));
+        let c = scaled.coeffs();
+        match self.degree() {
+            Some(1) => vec![-&c[0] / &c[1]],
+            Some(2) => {
+                let (a, b, c) = (c[2].numer(), c[1].numer(), c[0].numer());
+                let disc = b * b - BigInt::from(4) * a * c;
+                let root = match disc.isqrt() {
+                    Some(r) if &r * &r == disc => r,
+                    _ => return Vec::new(),
+                };
+                let two_a = BigInt::from(2) * a;
+                vec![
+                    Rational::new(-b - &root, two_a.clone()),
+                    Rational::new(-b + &root, two_a),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The positive divisors of a positive n, in increasing order.
+fn divisors(n: &BigInt) -> Vec<BigInt> {
+    let (mut small, mut large) = (Vec::new(), Vec::new());
+    let mut d = BigInt::one();
+    while &d * &d <= *n {
+        if (n % &d).is_zero() {
+            let other = n / &d;
+            if other != d {
+                large.push(other);
+            }
+            small.push(d.clone());
+        }
+        d = &d + &BigInt::one();
+    }
+    small.extend(large.into_iter().rev());
+    small
+}
+
+impl Neg for &Polynomial {
+    type Output = Polynomial;
+    fn neg(self) -> Polynomial {
+        Polynomial{coeffs: self.coeffs.iter().map(|c| -c).collect()}
+    }
+}
+
+impl Add for &Polynomial {
+    type Output = Polynomial;
+    fn add(self, rhs: &Polynomial) -> Polynomial {
+        let len = self.coeffs.len().max(rhs.coeffs.len());
+        let zero = Rational::zero();
+        Polynomial::from_coeffs(
+            (0..len)
+                .map(|i| self.coeffs.get(i).unwrap_or(&zero) + rhs.coeffs.get(i).unwrap_or(&zero))
+                .collect(),
+        )
+    }
+}
+
+impl Sub for &Polynomial {
+    type Output = Polynomial;
+    fn sub(self, rhs: &Polynomial) -> Polynomial {
+        self + &-rhs
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+    fn mul(self, rhs: &Polynomial) -> Polynomial {
+        if self.is_zero() || rhs.is_zero() {
+            return Polynomial::zero();
+        }
+        let mut out = vec![Rational::zero(); self.coeffs.len() + rhs.coeffs.len() - 1];
+        for (i, a) in self.coeffs.iter().enumerate() {
+            for (j, b) in rhs.coeffs.iter().enumerate() {
+                out[i + j] = &out[i + j] + &(a * b);
+            }
+        }
+        Polynomial::from_coeffs(out)
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut first = true;
+        for (i, val) in self.coeffs.iter().enumerate().rev() {
+            if val.is_zero() {
+                continue;
+            }
+            let mut val = val.clone();
+            if !first {
+                if val.is_negative() {
+                    write!(f, " - ")?;
+                    val = val.abs();
+                } else {
+                    write!(f, " + ")?;
+                }
+            }
+            first = false;
+            if i > 1 {
+                write!(f, "{} * X^{}", val, i)?;
+            } else if i == 1 {
+                write!(f, "{} * X", val)?;
+            } else {
+                write!(f, "{}", val)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A ratio of two polynomials, such as the result of evaluating an expression
+/// tree with +, -, * and / when one leaf is the unknown X. Kept in a canonical
+/// form: common factors are cancelled, coefficients are integers with no
+/// common divisor, and the denominator's leading coefficient is positive.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RationalFunction {
+    num: Polynomial,
+    den: Polynomial,
+}
+
+impl RationalFunction {
+    pub fn new(num: Polynomial, den: Polynomial) -> Self {
+        if den.is_zero() {
+            panic!("rational function with zero denominator");
+        }
+        let mut res = RationalFunction{num, den};
+        res.simplify();
+        res
+    }
+
+    pub fn constant(v: Rational) -> Self {
+        RationalFunction::new(Polynomial::constant(v), Polynomial::constant(Rational::one()))
+    }
+
+    /// The unknown, X.
+    pub fn var() -> Self {
+        RationalFunction::new(Polynomial::x(), Polynomial::constant(Rational::one()))
+    }
+
+    pub fn numerator(&self) -> &Polynomial {
+        &self.num
+    }
+
+    pub fn denominator(&self) -> &Polynomial {
+        &self.den
+    }
+
+    fn simplify(&mut self) {
+        if self.num.is_zero() {
+            self.den = Polynomial::constant(Rational::one());
+            return;
+        }
+        let g = self.num.gcd(&self.den);
+        self.num = self.num.div_rem(&g).0;
+        self.den = self.den.div_rem(&g).0;
+
+        /* Scale everything to integers, then divide out the common factor. */
+        let l = lcm(&self.num.denominator_lcm(), &self.den.denominator_lcm());
+        let lr = Rational::from(l.clone());
+        let content = self.num.coeffs.iter()
+            .chain(self.den.coeffs.iter())
+            .fold(BigInt::zero(), |g, c| g.gcd((c * &lr).numer()));
+        let mut factor = Rational::new(l, content);
+        if self.den.leading().is_negative() {
+            factor = -factor;
+        }
+        self.num = self.num.scale(&factor);
+        self.den = self.den.scale(&factor);
+    }
+
+    /// Evaluate at x, or None if the denominator is zero there.
+    pub fn eval(&self, x: &Rational) -> Option<Rational> {
+        let den = self.den.eval(x);
+        if den.is_zero() {
+            None
+        } else {
+            Some(self.num.eval(x) / den)
+        }
+    }
+
+    /// Find the rational values of X for which this evaluates to target.
+    pub fn solve(&self, target: &Rational) -> Result<Vec<Rational>, String> {
+        (self - &RationalFunction::constant(target.clone())).num.roots()
+    }
+}
+
+impl Neg for &RationalFunction {
+    type Output = RationalFunction;
+    fn neg(self) -> RationalFunction {
+        RationalFunction{num: -&self.num, den: self.den.clone()}
+    }
+}
+
+impl Add for &RationalFunction {
+    type Output = RationalFunction;
+    fn add(self, rhs: &RationalFunction) -> RationalFunction {
+        if self.den == rhs.den {
+            return RationalFunction::new(&self.num + &rhs.num, self.den.clone());
+        }
+        RationalFunction::new(&self.num * &rhs.den + &rhs.num * &self.den, &self.den * &rhs.den)
+    }
+}
+
+impl Sub for &RationalFunction {
+    type Output = RationalFunction;
+    fn sub(self, rhs: &RationalFunction) -> RationalFunction {
+        self + &-rhs
+    }
+}
+
+impl Mul for &RationalFunction {
+    type Output = RationalFunction;
+    fn mul(self, rhs: &RationalFunction) -> RationalFunction {
+        RationalFunction::new(&self.num * &rhs.num, &self.den * &rhs.den)
+    }
+}
+
+impl Div for &RationalFunction {
+    type Output = RationalFunction;
+    fn div(self, rhs: &RationalFunction) -> RationalFunction {
+        RationalFunction::new(&self.num * &rhs.den, &self.den * &rhs.num)
+    }
+}
+
+macro_rules! forward_binop {
+    ($t:ident: $($tr:ident $f:ident),*) => {$(
+        impl $tr<$t> for $t {
+            type Output = $t;
+            fn $f(self, rhs: $t) -> $t { (&self).$f(&rhs) }
+        }
+        impl $tr<&$t> for $t {
+            type Output = $t;
+            fn $f(self, rhs: &$t) -> $t { (&self).$f(rhs) }
+        }
+        impl $tr<$t> for &$t {
+            type Output = $t;
+            fn $f(self, rhs: $t) -> $t { self.$f(&rhs) }
+        }
+    )*};
+}
+forward_binop!(Polynomial: Add add, Sub sub, Mul mul);
+forward_binop!(RationalFunction: Add add, Sub sub, Mul mul, Div div);
+
+impl fmt::Display for RationalFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}) / ({})", self.num, self.den)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(n: i64, d: i64) -> Rational {
+        Rational::new(BigInt::from(n), BigInt::from(d))
+    }
+
+    fn poly(coeffs: &[i64]) -> Polynomial {
+        Polynomial::from_coeffs(coeffs.iter().map(|c| Rational::from(*c)).collect())
+    }
+
+    /// The polynomial with exactly these roots (with multiplicity), times k.
+    fn with_roots(k: i64, roots: &[Rational]) -> Polynomial {
+        roots.iter().fold(poly(&[k]), |p, root| p * Polynomial::from_coeffs(vec![-root, Rational::one()]))
+    }
+
+    #[test]
+    fn arithmetic() {
+        let (a, b) = (poly(&[1, 2]), poly(&[-1, 0, 3]));
+        assert_eq!(&a + &b, poly(&[0, 2, 3]));
+        assert_eq!(&a - &a, Polynomial::zero());
+        assert_eq!(&a * &b, poly(&[-1, -2, 3, 6]));
+        assert_eq!((&a * &Polynomial::zero()).degree(), None);
+        assert_eq!(b.eval(&r(1, 3)), r(-2, 3));
+        let (q, rem) = (&a * &b + poly(&[5])).div_rem(&b);
+        assert_eq!((q, rem), (a.clone(), poly(&[5])));
+        assert_eq!(poly(&[0, 0, 0]), Polynomial::zero());
+        assert_eq!(with_roots(2, &[r(1, 1), r(2, 1)]).gcd(&with_roots(3, &[r(2, 1), r(5, 1)])), with_roots(1, &[r(2, 1)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "polynomial division by zero")]
+    fn division_by_zero_polynomial() {
+        poly(&[1, 2]).div_rem(&Polynomial::zero());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Polynomial::zero().to_string(), "0");
+        assert_eq!(poly(&[5]).to_string(), "5");
+        assert_eq!(Polynomial::x().to_string(), "1 * X");
+        assert_eq!(poly(&[-4, 0, -3, 2]).to_string(), "2 * X^3 - 3 * X^2 - 4");
+        assert_eq!(Polynomial::from_coeffs(vec![r(1, 2), r(-2, 3)]).to_string(), "-2/3 * X + 1/2");
+        let f = RationalFunction::var() / RationalFunction::constant(r(2, 1));
+        assert_eq!(f.to_string(), "(1 * X) / (2)");
+    }
+
+    #[test]
+    fn roots_of_low_degree() {
+        assert!(Polynomial::zero().roots().is_err());
+        assert_eq!(poly(&[7]).roots(), Ok(vec![]));
+        assert_eq!(poly(&[3, 2]).roots(), Ok(vec![r(-3, 2)]));
+        assert_eq!(poly(&[-6, 1, 1]).roots(), Ok(vec![r(-3, 1), r(2, 1)]));
+        assert_eq!(poly(&[1, -2, 1]).roots(), Ok(vec![r(1, 1)]));
+        assert_eq!(poly(&[-2, 0, 1]).roots(), Ok(vec![]));
+        assert_eq!(poly(&[1, 0, 1]).roots(), Ok(vec![]));
+    }
+
+    #[test]
+    fn roots_of_higher_degree() {
+        let cases: Vec<Vec<Rational>> = vec![
+            vec![r(1, 1), r(2, 1), r(3, 1)],
+            vec![r(-1, 2), r(2, 3), r(5, 1), r(-7, 4)],
+            vec![r(0, 1), r(0, 1), r(3, 1), r(-3, 1)],
+            vec![r(2, 1), r(2, 1), r(2, 1), r(-1, 3), r(-1, 3)],
+            vec![r(10, 1); 6],
+        ];
+        for roots in cases {
+            let mut want = roots.clone();
+            want.sort();
+            want.dedup();
+            assert_eq!(with_roots(6, &roots).roots(), Ok(want.clone()), "{}", with_roots(6, &roots));
+            /* Non-integer coefficients get scaled up first. */
+            assert_eq!(with_roots(1, &roots).scale(&r(5, 7)).roots(), Ok(want));
+        }
+        /* Irrational and complex roots are left out: (X^2 - 2)(X^2 + 1)(X - 4). */
+        let p = poly(&[-2, 0, 1]) * poly(&[1, 0, 1]) * poly(&[-4, 1]);
+        assert_eq!(p.roots(), Ok(vec![r(4, 1)]));
+        assert_eq!((poly(&[-2, 0, 1]) * poly(&[1, 0, 1])).roots(), Ok(vec![]));
+    }
+
+    #[test]
+    fn rational_functions_simplify() {
+        let x = RationalFunction::var();
+        let one = RationalFunction::constant(Rational::one());
+        /* (X^2 - 1) / (X - 1) = X + 1 */
+        let f = (&x * &x - &one) / (&x - &one);
+        assert_eq!(f.numerator(), &poly(&[1, 1]));
+        assert_eq!(f.denominator(), &poly(&[1]));
+        /* Rational coefficients become coprime integers, denominator positive. */
+        let g = RationalFunction::new(
+            Polynomial::from_coeffs(vec![r(1, 2), r(1, 3)]),
+            Polynomial::from_coeffs(vec![r(-1, 4)]),
+        );
+        assert_eq!((g.numerator(), g.denominator()), (&poly(&[-6, -4]), &poly(&[3])));
+        assert_eq!(&x - &x, RationalFunction::constant(Rational::zero()));
+        assert_eq!(f.eval(&r(3, 1)), Some(r(4, 1)));
+        assert_eq!((&one / &x).eval(&Rational::zero()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero denominator")]
+    fn rational_function_division_by_zero() {
+        let _ = RationalFunction::var() / RationalFunction::constant(Rational::zero());
+    }
+
+    #[test]
+    fn solve() {
+        /* (3X + 4) / 2 - X = 5  =>  X = 6 */
+        let x = RationalFunction::var();
+        let c = |n: i64| RationalFunction::constant(Rational::from(n));
+        let f = (&c(3) * &x + c(4)) / c(2) - &x;
+        assert_eq!(f.solve(&Rational::from(5)), Ok(vec![r(6, 1)]));
+        /* X^3 = 8 */
+        assert_eq!((&x * &x * &x).solve(&Rational::from(8)), Ok(vec![r(2, 1)]));
+        assert!((&x - &x).solve(&Rational::zero()).is_err());
+    }
+}
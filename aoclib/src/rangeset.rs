@@ -0,0 +1,332 @@
+use std::cmp;
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+
+/**
+ * The RangeSet stores a set of integers, using a sorted list of ranges.
+ *
+ * There are a few obvious ways to store a set of integers:
+ *
+ * 1. An array of booleans. This requires an explicit, smallish domain of
+ *    integers -- since you can't have an infinite array, and allocating one
+ *    which has INT_MAX entries is probably prohibitive, depending on your
+ *    integer type. The array also requires storage size O(N) where N is size of
+ *    the domain of your set.  It features constant time membership testing,
+ *    but adding elements to the set is O(M), where M is the number of elements
+ *    you'd like to add.
+ * 2. A hash table. This is a decent general purpose solution, assuming a decent
+ *    hash function. The memory size is no longer limited to the domain of your
+ *    integers, but it is still O(M), as is the process of adding those M
+ *    elements to the set.
+ * 3. A sorted list. This is also a pretty good solution, but again, O(M) for
+ *    the size of your elements, and of course now the membership testing is
+ *    O(log M).
+ *
+ * If your M is large because of lots of contiguous ranges, you can instead use
+ * a sorted list of ranges! If we define K as the number of ranges that are
+ * members, then your storage size is O(K) and lookup time is O(log K).
+ *
+ * The list is kept canonical: ranges are sorted, never overlap, and are never
+ * adjacent (touching ranges are merged as they are added). Removal splits any
+ * range which it punches a hole into. Two sets hold the same members exactly
+ * when their lists are equal.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RangeSet {
+    list: Vec<(isize, isize)>,
+}
+
+impl RangeSet {
+    /** Create a new, empty set. */
+    pub fn new() -> Self {
+        RangeSet{list: Vec::new()}
+    }
+
+    /** Create a set containing just [start, end]. */
+    pub fn from_range(start: isize, end: isize) -> Self {
+        let mut set = Self::new();
+        set.add(start, end);
+        set
+    }
+
+    /**
+     * Add the range [start, end] -- INCLUSIVE on both ends -- to the set.
+     */
+    pub fn add(&mut self, start: isize, end: isize) {
+        if start > end {
+            return;
+        }
+        /* Find every range which overlaps or touches ours: lo is the first one
+         * ending at or after start - 1, and hi is the first one starting after
+         * end + 1. Everything in lo..hi gets merged into a single range. */
+        let lo = self.list.partition_point(|t| t.1 < start.saturating_sub(1));
+        let hi = self.list.partition_point(|t| t.0 <= end.saturating_add(1));
+        let mut merged = (start, end);
+        if lo < hi {
+            merged.0 = cmp::min(start, self.list[lo].0);
+            merged.1 = cmp::max(end, self.list[hi - 1].1);
+        }
+        self.list.splice(lo..hi, [merged]);
+    }
+
+    /**
+     * Remove the range [start, end] -- INCLUSIVE on both ends -- from the set.
+     * A range which straddles it is split in two.
+     */
+    pub fn remove(&mut self, start: isize, end: isize) {
+        if start > end {
+            return;
+        }
+        let lo = self.list.partition_point(|t| t.1 < start);
+        let hi = self.list.partition_point(|t| t.0 <= end);
+        if lo >= hi {
+            return;
+        }
+        /* Only the first and last affected ranges can stick out of the hole. */
+        let mut keep: Vec<(isize, isize)> = Vec::new();
+        if self.list[lo].0 < start {
+            keep.push((self.list[lo].0, start - 1));
+        }
+        if self.list[hi - 1].1 > end {
+            keep.push((end + 1, self.list[hi - 1].1));
+        }
+        self.list.splice(lo..hi, keep);
+    }
+
+    /** Return true if val is a member of the set. */
+    pub fn contains(&self, val: isize) -> bool {
+        let i = self.list.partition_point(|t| t.1 < val);
+        i < self.list.len() && self.list[i].0 <= val
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /** Return the total number of members in the set */
+    pub fn len(&self) -> isize {
+        self.list.iter().map(|t| t.1 - t.0 + 1).sum()
+    }
+
+    /** Return the smallest and largest members of the set */
+    pub fn min(&self) -> Option<isize> {
+        self.list.first().map(|t| t.0)
+    }
+
+    pub fn max(&self) -> Option<isize> {
+        self.list.last().map(|t| t.1)
+    }
+
+    /**
+     * Return a vector of ranges of members in the range [start, end]. You can
+     * easily iterate over this by doing:
+     *
+     * for range in self.get_ranges_between(start, end) {
+     *     for element in range.clone() {
+     *         do_something_with(element);
+     *     }
+     * }
+     */
+    pub fn get_ranges_between(&self, start: isize, end: isize) -> Vec<RangeInclusive<isize>> {
+        let i = self.list.partition_point(|t| t.1 < start);
+        self.list[i..]
+            .iter()
+            .take_while(|t| t.0 <= end)
+            .map(|t| cmp::max(t.0, start) ..= cmp::min(t.1, end))
+            .collect()
+    }
+
+    /** Return the number of members in the range [start, end] */
+    pub fn count(&self, start: isize, end: isize) -> isize {
+        self.get_ranges_between(start, end)
+            .iter()
+            .map(|r| *r.end() - *r.start() + 1)
+            .sum()
+    }
+
+    /** Iterate over the ranges of the set, in order */
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<isize>> + '_ {
+        self.list.iter().map(|t| t.0 ..= t.1)
+    }
+
+    /** Iterate over every member of the set, in order */
+    pub fn iter(&self) -> impl Iterator<Item = isize> + '_ {
+        self.ranges().flatten()
+    }
+
+    /** Return a set with the members of either set. */
+    pub fn union(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        for t in other.list.iter() {
+            res.add(t.0, t.1);
+        }
+        res
+    }
+
+    /** Return a set with the members that are in both sets. */
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut res = Self::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.list.len() && j < other.list.len() {
+            let (a, b) = (self.list[i], other.list[j]);
+            let start = cmp::max(a.0, b.0);
+            let end = cmp::min(a.1, b.1);
+            if start <= end {
+                /* Intersections of sorted, disjoint lists come out sorted and
+                 * disjoint, so we can push without merging. */
+                res.list.push((start, end));
+            }
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        res
+    }
+
+    /** Return a set with the members of self that are not in other. */
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        for t in other.list.iter() {
+            res.remove(t.0, t.1);
+        }
+        res
+    }
+
+    /** Return an inverted set, within [start, end]: every member of self is not
+     * a member of the complement, and vice versa. */
+    pub fn complement(&self, start: isize, end: isize) -> Self {
+        Self::from_range(start, end).difference(self)
+    }
+}
+
+impl FromIterator<RangeInclusive<isize>> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<isize>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for range in iter {
+            set.add(*range.start(), *range.end());
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn ranges(set: &RangeSet) -> Vec<(isize, isize)> {
+        set.ranges().map(|r| (*r.start(), *r.end())).collect()
+    }
+
+    #[test]
+    fn add_merges_adjacent_and_overlapping() {
+        let mut set = RangeSet::new();
+        set.add(1, 3);
+        set.add(4, 6);
+        assert_eq!(ranges(&set), vec![(1, 6)]);
+        set.add(10, 12);
+        set.add(8, 8);
+        assert_eq!(ranges(&set), vec![(1, 6), (8, 8), (10, 12)]);
+        set.add(7, 9);
+        assert_eq!(ranges(&set), vec![(1, 12)]);
+        set.add(0, 20);
+        assert_eq!(ranges(&set), vec![(0, 20)]);
+    }
+
+    #[test]
+    fn add_nested_and_empty() {
+        let mut set = RangeSet::from_range(0, 10);
+        set.add(3, 5);
+        assert_eq!(ranges(&set), vec![(0, 10)]);
+        set.add(5, 4);
+        assert_eq!(ranges(&set), vec![(0, 10)]);
+        assert!(RangeSet::from_range(3, 2).is_empty());
+    }
+
+    #[test]
+    fn remove_splits() {
+        let mut set = RangeSet::from_range(0, 10);
+        set.remove(4, 6);
+        assert_eq!(ranges(&set), vec![(0, 3), (7, 10)]);
+        /* Removing exactly up to an edge leaves nothing behind on that side. */
+        set.remove(0, 3);
+        assert_eq!(ranges(&set), vec![(7, 10)]);
+        set.remove(10, 10);
+        assert_eq!(ranges(&set), vec![(7, 9)]);
+        /* Adjacent but not overlapping: no change. */
+        set.remove(10, 15);
+        set.remove(0, 6);
+        assert_eq!(ranges(&set), vec![(7, 9)]);
+        set.remove(9, 7);
+        assert_eq!(ranges(&set), vec![(7, 9)]);
+        set.remove(-100, 100);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn remove_spanning_several() {
+        let mut set: RangeSet = [0..=2, 5..=7, 10..=12, 15..=17].into_iter().collect();
+        set.remove(1, 16);
+        assert_eq!(ranges(&set), vec![(0, 0), (17, 17)]);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a: RangeSet = [0..=5, 10..=15].into_iter().collect();
+        let b: RangeSet = [3..=11, 15..=20].into_iter().collect();
+        assert_eq!(ranges(&a.union(&b)), vec![(0, 20)]);
+        assert_eq!(ranges(&a.intersection(&b)), vec![(3, 5), (10, 11), (15, 15)]);
+        assert_eq!(ranges(&a.difference(&b)), vec![(0, 2), (12, 14)]);
+        assert_eq!(ranges(&a.complement(-2, 17)), vec![(-2, -1), (6, 9), (16, 17)]);
+        let empty = RangeSet::new();
+        assert_eq!(a.union(&empty), a);
+        assert!(a.intersection(&empty).is_empty());
+        assert_eq!(a.difference(&empty), a);
+        assert_eq!(ranges(&empty.complement(1, 3)), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn queries() {
+        let set: RangeSet = [0..=5, 10..=15].into_iter().collect();
+        assert_eq!(set.len(), 12);
+        assert_eq!((set.min(), set.max()), (Some(0), Some(15)));
+        assert!(set.contains(5) && !set.contains(6) && set.contains(10));
+        assert_eq!(set.get_ranges_between(3, 12), vec![3..=5, 10..=12]);
+        assert_eq!(set.count(6, 9), 0);
+        assert_eq!(set.count(-5, 100), 12);
+        assert_eq!(RangeSet::new().min(), None);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut seed = 12345u64;
+        let mut rand = |n: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n) as isize
+        };
+        for _ in 0..200 {
+            let mut set = RangeSet::new();
+            let mut brute: BTreeSet<isize> = BTreeSet::new();
+            for _ in 0..20 {
+                let start = rand(40) - 5;
+                let end = start + rand(8) - 1;
+                if rand(3) == 0 {
+                    set.remove(start, end);
+                    for v in start..=end {
+                        brute.remove(&v);
+                    }
+                } else {
+                    set.add(start, end);
+                    brute.extend(start..=end);
+                }
+                assert_eq!(set.iter().collect::<Vec<_>>(), brute.iter().copied().collect::<Vec<_>>());
+                /* Canonical: sorted, with a gap between neighbours. */
+                assert!(set.list.windows(2).all(|w| w[0].1 + 1 < w[1].0));
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::bigint::BigInt;
+
+/// An exact fraction. Always kept in lowest terms with a positive denominator,
+/// so derived equality and hashing compare values.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+impl Rational {
+    /// Create num / den, panicking if den is zero.
+    pub fn new(num: BigInt, den: BigInt) -> Self {
+        if den.is_zero() {
+            panic!("rational with zero denominator");
+        }
+        let g = num.gcd(&den);
+        let (mut num, mut den) = (&num / &g, &den / &g);
+        if den.is_negative() {
+            num = -num;
+            den = -den;
+        }
+        Rational{num, den}
+    }
+
+    pub fn zero() -> Self {
+        Rational::from(0)
+    }
+
+    pub fn one() -> Self {
+        Rational::from(1)
+    }
+
+    pub fn numer(&self) -> &BigInt {
+        &self.num
+    }
+
+    pub fn denom(&self) -> &BigInt {
+        &self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == BigInt::one()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.num.is_negative()
+    }
+
+    pub fn abs(&self) -> Self {
+        Rational{num: self.num.abs(), den: self.den.clone()}
+    }
+
+    /// Panics when dividing by zero, just like integer division.
+    pub fn recip(&self) -> Self {
+        Rational::new(self.den.clone(), self.num.clone())
+    }
+}
+
+impl<T: Into<BigInt>> From<T> for Rational {
+    fn from(v: T) -> Self {
+        Rational{num: v.into(), den: BigInt::one()}
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.num * &other.den).cmp(&(&other.num * &self.den))
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        Rational{num: -&self.num, den: self.den.clone()}
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        Rational{num: -self.num, den: self.den}
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+    fn add(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.den + &rhs.num * &self.den, &self.den * &rhs.den)
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+    fn sub(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.den - &rhs.num * &self.den, &self.den * &rhs.den)
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+    fn mul(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.num, &self.den * &rhs.den)
+    }
+}
+
+impl Div for &Rational {
+    type Output = Rational;
+    fn div(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.den, &self.den * &rhs.num)
+    }
+}
+
+macro_rules! forward_binop {
+    ($($tr:ident $f:ident),*) => {$(
+        impl $tr<Rational> for Rational {
+            type Output = Rational;
+            fn $f(self, rhs: Rational) -> Rational { (&self).$f(&rhs) }
+        }
+        impl $tr<&Rational> for Rational {
+            type Output = Rational;
+            fn $f(self, rhs: &Rational) -> Rational { (&self).$f(rhs) }
+        }
+        impl $tr<Rational> for &Rational {
+            type Output = Rational;
+            fn $f(self, rhs: Rational) -> Rational { self.$f(&rhs) }
+        }
+    )*};
+}
+forward_binop!(Add add, Sub sub, Mul mul, Div div);
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl fmt::Debug for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(n: i64, d: i64) -> Rational {
+        Rational::new(BigInt::from(n), BigInt::from(d))
+    }
+
+    #[test]
+    fn lowest_terms_with_positive_denominator() {
+        let half = r(-2, -4);
+        assert_eq!((half.numer(), half.denom()), (&BigInt::from(1), &BigInt::from(2)));
+        let neg = r(6, -9);
+        assert_eq!((neg.numer(), neg.denom()), (&BigInt::from(-2), &BigInt::from(3)));
+        assert_eq!(r(0, -5), Rational::zero());
+        assert_eq!(r(0, -5).denom(), &BigInt::one());
+        assert!(r(10, 5).is_integer());
+        assert_eq!(r(3, 4), r(-6, -8));
+    }
+
+    #[test]
+    fn arithmetic_and_ordering() {
+        assert_eq!(r(1, 2) + r(1, 3), r(5, 6));
+        assert_eq!(r(1, 2) - r(3, 4), r(-1, 4));
+        assert_eq!(r(2, 3) * r(9, -4), r(-3, 2));
+        assert_eq!(r(2, 3) / r(-4, 9), r(-3, 2));
+        assert_eq!(-r(1, 2), r(-1, 2));
+        assert_eq!(r(-5, 3).abs(), r(5, 3));
+        assert_eq!(r(-5, 3).recip(), r(-3, 5));
+        assert!(r(-1, 2) < r(-1, 3) && r(1, 3) < r(1, 2) && r(7, 7) == Rational::one());
+        let mut v = vec![r(3, 2), r(-1, 1), r(1, 3), r(0, 1)];
+        v.sort();
+        assert_eq!(v, vec![r(-1, 1), r(0, 1), r(1, 3), r(3, 2)]);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(r(4, 2).to_string(), "2");
+        assert_eq!(r(3, -6).to_string(), "-1/2");
+        assert_eq!(format!("{:?}", r(0, 3)), "0");
+    }
+
+    #[test]
+    #[should_panic(expected = "zero denominator")]
+    fn zero_denominator() {
+        r(1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "zero denominator")]
+    fn division_by_zero() {
+        let _ = r(1, 2) / Rational::zero();
+    }
+
+    #[test]
+    #[should_panic(expected = "zero denominator")]
+    fn reciprocal_of_zero() {
+        Rational::zero().recip();
+    }
+}
@@ -0,0 +1,234 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// The outcome of a search: the best known cost to each visited node, the
+/// node we came from to get there, and the first target node reached (if a
+/// target was requested and found).
+#[derive(Clone, Debug)]
+pub struct SearchResult<N, C> {
+    pub dist: HashMap<N, C>,
+    pub prev: HashMap<N, N>,
+    pub target: Option<N>,
+}
+
+impl<N, C> SearchResult<N, C>
+where N: Clone + Eq + Hash,
+      C: Copy {
+    pub fn distance(&self, node: &N) -> Option<C> {
+        self.dist.get(node).copied()
+    }
+
+    /// Cost to the target which ended the search.
+    pub fn target_distance(&self) -> Option<C> {
+        self.target.as_ref().and_then(|t| self.distance(t))
+    }
+
+    /// Walk the predecessor map back from `node`, returning the path from its
+    /// starting node (inclusive) to `node` (inclusive).
+    pub fn path_to(&self, node: &N) -> Option<Vec<N>> {
+        if !self.dist.contains_key(node) {
+            return None;
+        }
+        let mut path = vec![node.clone()];
+        let mut cur = node;
+        while let Some(prev) = self.prev.get(cur) {
+            path.push(prev.clone());
+            cur = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn target_path(&self) -> Option<Vec<N>> {
+        self.target.as_ref().and_then(|t| self.path_to(t))
+    }
+}
+
+/// Breadth-first search where every step costs 1. All of `starts` begin at
+/// distance 0. The search stops as soon as a node satisfying `is_target` is
+/// dequeued; pass `|_| false` to explore everything reachable.
+pub fn bfs<N, I, S, FN, FT>(starts: S, mut neighbours: FN, mut is_target: FT) -> SearchResult<N, usize>
+where N: Clone + Eq + Hash,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = N>,
+      FN: FnMut(&N) -> I,
+      FT: FnMut(&N) -> bool {
+    let mut res = SearchResult{dist: HashMap::new(), prev: HashMap::new(), target: None};
+    let mut q: VecDeque<N> = VecDeque::new();
+    for start in starts {
+        if !res.dist.contains_key(&start) {
+            res.dist.insert(start.clone(), 0);
+            q.push_back(start);
+        }
+    }
+    while let Some(cur) = q.pop_front() {
+        if is_target(&cur) {
+            res.target = Some(cur);
+            break;
+        }
+        let len = res.dist[&cur];
+        for next in neighbours(&cur) {
+            if res.dist.contains_key(&next) {
+                continue;
+            }
+            res.dist.insert(next.clone(), len + 1);
+            res.prev.insert(next.clone(), cur.clone());
+            q.push_back(next);
+        }
+    }
+    res
+}
+
+/// Dijkstra's algorithm: `neighbours` yields `(node, step_cost)` pairs, and
+/// costs must be non-negative.
+pub fn dijkstra<N, C, I, S, FN, FT>(starts: S, neighbours: FN, is_target: FT) -> SearchResult<N, C>
+where N: Clone + Ord + Hash,
+      C: Copy + Ord + Default + Add<Output = C>,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = (N, C)>,
+      FN: FnMut(&N) -> I,
+      FT: FnMut(&N) -> bool {
+    astar(starts, neighbours, |_| C::default(), is_target)
+}
+
+/// A* search. `heuristic` must never overestimate the remaining cost to the
+/// nearest target, otherwise the result may not be optimal. With a heuristic
+/// of zero this is just Dijkstra's algorithm.
+pub fn astar<N, C, I, S, FN, FH, FT>(
+    starts: S,
+    mut neighbours: FN,
+    mut heuristic: FH,
+    mut is_target: FT,
+) -> SearchResult<N, C>
+where N: Clone + Ord + Hash,
+      C: Copy + Ord + Default + Add<Output = C>,
+      S: IntoIterator<Item = N>,
+      I: IntoIterator<Item = (N, C)>,
+      FN: FnMut(&N) -> I,
+      FH: FnMut(&N) -> C,
+      FT: FnMut(&N) -> bool {
+    let mut res = SearchResult{dist: HashMap::new(), prev: HashMap::new(), target: None};
+    let mut heap: BinaryHeap<Reverse<(C, C, N)>> = BinaryHeap::new();
+    for start in starts {
+        if !res.dist.contains_key(&start) {
+            res.dist.insert(start.clone(), C::default());
+            heap.push(Reverse((heuristic(&start), C::default(), start)));
+        }
+    }
+    while let Some(Reverse((_, cost, cur))) = heap.pop() {
+        if res.dist.get(&cur).is_some_and(|best| *best < cost) {
+            continue; // stale entry, we've since found a cheaper route
+        }
+        if is_target(&cur) {
+            res.target = Some(cur);
+            break;
+        }
+        for (next, step) in neighbours(&cur) {
+            let next_cost = cost + step;
+            if res.dist.get(&next).is_some_and(|best| *best <= next_cost) {
+                continue;
+            }
+            res.dist.insert(next.clone(), next_cost);
+            res.prev.insert(next.clone(), cur.clone());
+            heap.push(Reverse((next_cost + heuristic(&next), next_cost, next)));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path 0 - 1 - ... - (n-1), listing the lower neighbour first.
+    fn line(n: i32) -> impl FnMut(&i32) -> Vec<i32> {
+        move |i| [i - 1, i + 1].into_iter().filter(|j| (0..n).contains(j)).collect()
+    }
+
+    #[test]
+    fn bfs_from_several_starts() {
+        let res = bfs([0, 9, 0], line(10), |_| false);
+        let dist: Vec<usize> = (0..10).map(|i| res.distance(&i).unwrap()).collect();
+        assert_eq!(dist, vec![0, 1, 2, 3, 4, 4, 3, 2, 1, 0]);
+        assert_eq!(res.target, None);
+        assert_eq!(res.path_to(&3), Some(vec![0, 1, 2, 3]));
+        assert_eq!(res.path_to(&6), Some(vec![9, 8, 7, 6]));
+        assert_eq!(res.path_to(&9), Some(vec![9]));
+        assert_eq!(res.path_to(&10), None);
+    }
+
+    #[test]
+    fn bfs_stops_at_first_target() {
+        let res = bfs([5], line(100), |i| *i == 2 || *i == 8);
+        assert_eq!(res.target, Some(2));
+        assert_eq!(res.target_distance(), Some(3));
+        assert_eq!(res.target_path(), Some(vec![5, 4, 3, 2]));
+        /* Nothing more than one step past the target's distance is seen. */
+        assert!(res.dist.values().all(|d| *d <= 4));
+        assert_eq!(res.distance(&0), None);
+
+        let none = bfs([5], line(10), |i| *i == 50);
+        assert_eq!((none.target, none.target_path()), (None, None));
+        assert_eq!(none.dist.len(), 10);
+    }
+
+    /// A small weighted directed graph, with a cheap long way round.
+    fn weighted(n: &char) -> Vec<(char, u32)> {
+        match n {
+            'a' => vec![('b', 7), ('c', 1)],
+            'b' => vec![('e', 1)],
+            'c' => vec![('d', 2), ('b', 9)],
+            'd' => vec![('b', 1), ('e', 10)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn dijkstra_takes_the_cheap_way_round() {
+        let res = dijkstra(['a'], weighted, |n| *n == 'e');
+        assert_eq!(res.target_distance(), Some(5));
+        assert_eq!(res.target_path(), Some(vec!['a', 'c', 'd', 'b', 'e']));
+        assert_eq!(res.prev.get(&'b'), Some(&'d'));
+
+        let all = dijkstra(['a'], weighted, |_| false);
+        assert_eq!(all.distance(&'d'), Some(3));
+        assert_eq!(all.distance(&'z'), None);
+        /* Starting from both ends of the cheap route. */
+        let multi = dijkstra(['a', 'd'], weighted, |_| false);
+        assert_eq!(multi.distance(&'b'), Some(1));
+        assert_eq!(multi.path_to(&'e'), Some(vec!['d', 'b', 'e']));
+    }
+
+    #[test]
+    fn astar_agrees_with_dijkstra() {
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        for _ in 0..50 {
+            /* A random grid of step costs 1..=9, walked in four directions. */
+            let size = 12i32;
+            let cost: Vec<u32> = (0..size * size).map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                (seed % 9) as u32 + 1
+            }).collect();
+            let neighbours = |&(x, y): &(i32, i32)| {
+                [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                    .into_iter()
+                    .filter(|(x, y)| (0..size).contains(x) && (0..size).contains(y))
+                    .map(|(x, y)| ((x, y), cost[(y * size + x) as usize]))
+                    .collect::<Vec<_>>()
+            };
+            let goal = (size - 1, size - 1);
+            let manhattan = |&(x, y): &(i32, i32)| (goal.0 - x + goal.1 - y) as u32;
+            let plain = dijkstra([(0, 0)], neighbours, |n| *n == goal);
+            let fast = astar([(0, 0)], neighbours, manhattan, |n| *n == goal);
+            assert_eq!(fast.target_distance(), plain.target_distance());
+            let path = fast.target_path().unwrap();
+            let walked: u32 = path.windows(2).map(|w| cost[(w[1].1 * size + w[1].0) as usize]).sum();
+            assert_eq!(Some(walked), plain.target_distance());
+            assert!(fast.dist.len() <= plain.dist.len());
+        }
+    }
+}
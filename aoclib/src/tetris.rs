@@ -0,0 +1,251 @@
+/// A rigid block, stored one bitmask per row from the bottom up. Bit x is set
+/// when column x (counting from the left edge of the shape) is filled.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Shape {
+    rows: Vec<u32>,
+    width: usize,
+}
+
+impl Shape {
+    /// Parse ASCII art where '#' is filled and '.' is empty. The text is drawn
+    /// top row first, as it would appear on screen.
+    pub fn parse(art: &str) -> Result<Shape, String> {
+        let mut rows = Vec::new();
+        let mut width = 0;
+        for line in art.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if line.len() > 32 {
+                return Err(format!("shape row too wide: {}", line));
+            }
+            let mut row = 0u32;
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '#' => row |= 1 << x,
+                    '.' => {}
+                    _ => return Err(format!("invalid shape character: {:?}", c)),
+                }
+            }
+            width = width.max(line.len());
+            rows.push(row);
+        }
+        if rows.iter().all(|r| *r == 0) {
+            return Err("shape has no filled cells".into());
+        }
+        rows.reverse();
+        Ok(Shape{rows, width})
+    }
+
+    /// Parse several shapes separated by blank lines.
+    pub fn parse_all(text: &str) -> Result<Vec<Shape>, String> {
+        text.trim().split("\n\n").map(Shape::parse).collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Jet {
+    Left,
+    Right,
+}
+
+impl Jet {
+    pub fn parse_all(s: &str) -> Result<Vec<Jet>, String> {
+        s.trim()
+            .chars()
+            .map(|c| match c {
+                '<' => Ok(Jet::Left),
+                '>' => Ok(Jet::Right),
+                _ => Err(format!("invalid jet direction character: {:?}", c)),
+            })
+            .collect()
+    }
+}
+
+/// A narrow chamber which rocks fall into, one at a time. Each rock appears
+/// `spawn_x` columns from the left wall and with `spawn_gap` empty rows below
+/// it, then alternates between being pushed by the next jet and falling one
+/// row, until it can't fall any further. Shapes and jets are both used in
+/// order, wrapping around forever.
+#[derive(Clone, Debug)]
+pub struct Chamber {
+    width: usize,
+    rows: Vec<u32>,
+    /* For each column, one more than the row of its highest rock (0 if
+     * empty), kept up to date as rocks land. */
+    tops: Vec<usize>,
+    shapes: Vec<Shape>,
+    jets: Vec<Jet>,
+    shape_index: usize,
+    jet_index: usize,
+    dropped: usize,
+    pub spawn_x: usize,
+    pub spawn_gap: usize,
+}
+
+impl Chamber {
+    pub fn new(width: usize, shapes: Vec<Shape>, jets: Vec<Jet>) -> Result<Chamber, String> {
+        if width > 32 {
+            return Err(format!("chamber width {} is wider than 32", width));
+        }
+        if shapes.is_empty() || jets.is_empty() {
+            return Err("need at least one shape and one jet".into());
+        }
+        if let Some(s) = shapes.iter().find(|s| s.width > width) {
+            return Err(format!("shape of width {} doesn't fit in the chamber", s.width));
+        }
+        Ok(Chamber{
+            width, rows: Vec::new(), tops: vec![0; width], shapes, jets,
+            shape_index: 0, jet_index: 0, dropped: 0,
+            spawn_x: 2, spawn_gap: 3,
+        })
+    }
+
+    /// Height of the tower of settled rocks.
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Number of rocks which have come to rest.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    fn fits(&self, shape: &Shape, x: isize, y: usize) -> bool {
+        if x < 0 || x as usize + shape.width > self.width {
+            return false;
+        }
+        shape.rows.iter().enumerate().all(|(i, row)| {
+            self.rows.get(y + i).is_none_or(|r| r & (row << x) == 0)
+        })
+    }
+
+    /// Drop the next rock and let it come to rest.
+    pub fn drop_rock(&mut self) {
+        let shape = &self.shapes[self.shape_index];
+        let mut x = self.spawn_x as isize;
+        let mut y = self.height() + self.spawn_gap;
+        loop {
+            let dx = match self.jets[self.jet_index] {
+                Jet::Left => -1,
+                Jet::Right => 1,
+            };
+            self.jet_index = (self.jet_index + 1) % self.jets.len();
+            if self.fits(shape, x + dx, y) {
+                x += dx;
+            }
+            if y == 0 || !self.fits(shape, x, y - 1) {
+                break;
+            }
+            y -= 1;
+        }
+        let needed = y + shape.rows.len();
+        if needed > self.rows.len() {
+            self.rows.resize(needed, 0);
+        }
+        for (i, row) in shape.rows.iter().enumerate() {
+            self.rows[y + i] |= row << x;
+            for bx in 0..shape.width {
+                if row & (1 << bx) != 0 {
+                    let top = &mut self.tops[x as usize + bx];
+                    *top = (*top).max(y + i + 1);
+                }
+            }
+        }
+        self.shape_index = (self.shape_index + 1) % self.shapes.len();
+        self.dropped += 1;
+    }
+
+    /// For each column, how far below the top of the tower its highest rock
+    /// is (or the full height, if the column is empty).
+    pub fn surface_profile(&self) -> Vec<usize> {
+        self.tops.iter().map(|top| self.height() - top).collect()
+    }
+
+    /// A fingerprint of the state for cycle detection: the position in the
+    /// shape and jet sequences, plus the surface profile. Two states with the
+    /// same fingerprint will almost certainly grow the same way from then on.
+    pub fn fingerprint(&self) -> (usize, usize, Vec<usize>) {
+        (self.shape_index, self.jet_index, self.surface_profile())
+    }
+
+    /// Draw the top `n` rows of the tower.
+    pub fn render(&self, n: usize) -> String {
+        let mut out = String::new();
+        for row in self.rows.iter().rev().take(n) {
+            out.push('|');
+            for x in 0..self.width {
+                out.push(if row & (1 << x) != 0 { '#' } else { '.' });
+            }
+            out.push_str("|\n");
+        }
+        if n >= self.rows.len() {
+            out.push('+');
+            out.push_str(&"-".repeat(self.width));
+            out.push_str("+\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHAPES: &str = "
+####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##
+";
+
+    const JETS: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+
+    #[test]
+    fn profile_matches_full_scan() {
+        let shapes = Shape::parse_all(SHAPES).unwrap();
+        let jets = Jet::parse_all(JETS).unwrap();
+        let mut chamber = Chamber::new(7, shapes, jets).unwrap();
+        for _ in 0..500 {
+            chamber.drop_rock();
+            let scanned: Vec<usize> = (0..chamber.width)
+                .map(|x| {
+                    chamber.rows.iter().rev()
+                        .position(|r| r & (1 << x) != 0)
+                        .unwrap_or(chamber.height())
+                })
+                .collect();
+            assert_eq!(chamber.surface_profile(), scanned);
+        }
+    }
+
+    #[test]
+    fn example_height() {
+        let shapes = Shape::parse_all(SHAPES).unwrap();
+        let jets = Jet::parse_all(JETS).unwrap();
+        let mut chamber = Chamber::new(7, shapes, jets).unwrap();
+        assert_eq!(chamber.surface_profile(), vec![0; 7]);
+        for _ in 0..2022 {
+            chamber.drop_rock();
+        }
+        assert_eq!(chamber.height(), 3068);
+    }
+}
@@ -0,0 +1,432 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::graph;
+
+/*
+ * A tiny virtual machine for the puzzles with a toy instruction set. The
+ * puzzle defines its instructions by implementing Instruction: how to parse
+ * one, how many cycles it takes, and what it does to the machine state. The
+ * machine supplies the rest: the program counter, stepping, a cycle counter
+ * with a hook called during every cycle, loop detection, and traces.
+ *
+ * Loop detection assumes control flow depends only on the program counter,
+ * which holds as long as jumps are unconditional: reaching the same
+ * instruction twice then means running forever. The same assumption lets
+ * repair() work out the control flow graph from the program alone.
+ */
+
+pub trait Instruction: Clone + Sized {
+    type State: Clone;
+
+    fn parse(line: &str) -> Result<Self, String>;
+
+    /// How many cycles the instruction takes to finish.
+    fn cycles(&self) -> usize {
+        1
+    }
+
+    /// Apply the instruction, returning how far to move the program counter
+    /// (1 to carry on to the next instruction).
+    fn execute(&self, state: &mut Self::State) -> isize;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VmError {
+    /// A line (numbered from 1) which isn't a valid instruction.
+    Parse{line: usize, msg: String},
+    /// A jump to somewhere other than an instruction or just past the end.
+    BadJump{from: usize, to: isize},
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::Parse{line, msg} => write!(f, "line {}: {}", line, msg),
+            VmError::BadJump{from, to} => write!(f, "bad jump target {} from instruction {}", to, from),
+        }
+    }
+}
+
+impl Error for VmError {}
+
+/// Parse one instruction per line, skipping blank lines.
+pub fn parse_program<I, S>(lines: impl IntoIterator<Item = S>) -> Result<Vec<I>, VmError>
+where I: Instruction,
+      S: AsRef<str> {
+    let mut program = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        let line = line.as_ref().trim();
+        if line.is_empty() {
+            continue;
+        }
+        program.push(I::parse(line).map_err(|msg| VmError::Parse{line: i + 1, msg})?);
+    }
+    Ok(program)
+}
+
+/// Why the machine stopped running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Halt {
+    /// The program counter went just past the last instruction.
+    Finished,
+    /// The instruction here was about to run for the second time.
+    Loop(usize),
+}
+
+/// One executed instruction, with the machine state after it ran.
+#[derive(Clone, Debug)]
+pub struct TraceEntry<I: Instruction> {
+    /// The cycle it started on, counting from 1.
+    pub cycle: usize,
+    pub pc: usize,
+    pub instr: I,
+    pub state: I::State,
+}
+
+pub struct Machine<'a, I: Instruction> {
+    program: &'a [I],
+    pc: usize,
+    cycle: usize,
+    state: I::State,
+    seen: Vec<bool>,
+    trace: Option<Vec<TraceEntry<I>>>,
+}
+
+impl<'a, I: Instruction> Machine<'a, I> {
+    pub fn new(program: &'a [I], state: I::State) -> Self {
+        Machine{program, pc: 0, cycle: 0, state, seen: vec![false; program.len()], trace: None}
+    }
+
+    /// Record every instruction executed from now on.
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The number of cycles completed so far.
+    pub fn cycles(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn state(&self) -> &I::State {
+        &self.state
+    }
+
+    pub fn into_state(self) -> I::State {
+        self.state
+    }
+
+    /// Everything executed since tracing was turned on.
+    pub fn trace(&self) -> &[TraceEntry<I>] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    /// Where the machine would stop before running the next instruction,
+    /// if it would.
+    fn halted(&self) -> Option<Halt> {
+        if self.pc == self.program.len() {
+            Some(Halt::Finished)
+        } else if self.seen[self.pc] {
+            Some(Halt::Loop(self.pc))
+        } else {
+            None
+        }
+    }
+
+    /// Run one instruction, calling `hook` with the cycle number (from 1) and
+    /// the state during each of its cycles, before it takes effect. Returns
+    /// whether the machine has stopped, without running anything if so.
+    pub fn step_with<F>(&mut self, hook: &mut F) -> Result<Option<Halt>, VmError>
+    where F: FnMut(usize, &I::State) {
+        if let Some(halt) = self.halted() {
+            return Ok(Some(halt));
+        }
+        let pc = self.pc;
+        let instr = &self.program[pc];
+        self.seen[pc] = true;
+        let start = self.cycle + 1;
+        for _ in 0..instr.cycles() {
+            self.cycle += 1;
+            hook(self.cycle, &self.state);
+        }
+        let offset = instr.execute(&mut self.state);
+        let target = pc as isize + offset;
+        if target < 0 || target > self.program.len() as isize {
+            return Err(VmError::BadJump{from: pc, to: target});
+        }
+        self.pc = target as usize;
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry{cycle: start, pc, instr: instr.clone(), state: self.state.clone()});
+        }
+        Ok(self.halted())
+    }
+
+    pub fn step(&mut self) -> Result<Option<Halt>, VmError> {
+        self.step_with(&mut |_, _| {})
+    }
+
+    /// Run until the program finishes or starts to loop.
+    pub fn run_with<F>(&mut self, mut hook: F) -> Result<Halt, VmError>
+    where F: FnMut(usize, &I::State) {
+        loop {
+            if let Some(halt) = self.step_with(&mut hook)? {
+                return Ok(halt);
+            }
+        }
+    }
+
+    pub fn run(&mut self) -> Result<Halt, VmError> {
+        self.run_with(|_, _| {})
+    }
+}
+
+/// A single-instruction patch which makes a program finish.
+#[derive(Clone, Debug)]
+pub struct Repair<I: Instruction> {
+    pub pc: usize,
+    /// The replacement instruction.
+    pub instr: I,
+    /// The machine state when the patched program finishes.
+    pub state: I::State,
+}
+
+/*
+ * Rather than trying every patch and rerunning the whole program, build the
+ * control flow graph once and work backward from the end to find every
+ * instruction which leads to it. A looping program runs the same path up to
+ * the loop whatever we patch later, so the fix must be on that path: the
+ * first instruction there which a patch points at one of the finishing
+ * instructions. (The rest of the way can't go back through the patch, or the
+ * original program would have finished too.) All linear in the program size.
+ */
+
+/// Find the first instruction on the path of a looping program which, when
+/// replaced by `patch` (None meaning it can't be patched), makes the program
+/// finish. None if the program already finishes or no patch saves it. Jump
+/// targets come from executing each instruction against a copy of `init`.
+pub fn repair<I, F>(program: &[I], init: I::State, patch: F) -> Result<Option<Repair<I>>, VmError>
+where I: Instruction,
+      F: Fn(&I) -> Option<I> {
+    let n = program.len();
+    let target = |pc: usize, instr: &I| -> Option<usize> {
+        let to = pc as isize + instr.execute(&mut init.clone());
+        (0..=n as isize).contains(&to).then_some(to as usize)
+    };
+
+    /* Every pc from which running reaches the end. */
+    let finishes = graph::reverse_reachable(0..n, |pc| target(*pc, &program[*pc]), &n);
+    if finishes.contains(&0) {
+        return Ok(None);
+    }
+
+    let mut seen = vec![false; n];
+    let mut pc = 0;
+    while pc < n && !seen[pc] {
+        seen[pc] = true;
+        if let Some(fixed) = patch(&program[pc]) {
+            if target(pc, &fixed).is_some_and(|to| finishes.contains(&to)) {
+                let mut patched = program.to_vec();
+                patched[pc] = fixed.clone();
+                let mut machine = Machine::new(&patched, init);
+                machine.run()?;
+                return Ok(Some(Repair{pc, instr: fixed, state: machine.into_state()}));
+            }
+        }
+        let to = pc as isize + program[pc].execute(&mut init.clone());
+        if !(0..=n as isize).contains(&to) {
+            return Err(VmError::BadJump{from: pc, to});
+        }
+        pc = to as usize;
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* A toy instruction set: an accumulator, relative jumps, and a two-cycle
+     * add like 2022 day 10's addx. */
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    enum Op {
+        Nop(isize),
+        Acc(isize),
+        Jmp(isize),
+        Addx(isize),
+    }
+
+    impl Instruction for Op {
+        type State = isize;
+
+        fn parse(line: &str) -> Result<Self, String> {
+            let (name, arg) = match line.split_once(' ') {
+                Some((name, arg)) => (name, arg.parse::<isize>().map_err(|e| e.to_string())?),
+                None => (line, 0),
+            };
+            match name {
+                "nop" | "noop" => Ok(Op::Nop(arg)),
+                "acc" => Ok(Op::Acc(arg)),
+                "jmp" => Ok(Op::Jmp(arg)),
+                "addx" => Ok(Op::Addx(arg)),
+                _ => Err(format!("unknown instruction {:?}", name)),
+            }
+        }
+
+        fn cycles(&self) -> usize {
+            if let Op::Addx(_) = self { 2 } else { 1 }
+        }
+
+        fn execute(&self, state: &mut isize) -> isize {
+            match self {
+                Op::Nop(_) => 1,
+                Op::Acc(v) | Op::Addx(v) => {
+                    *state += v;
+                    1
+                }
+                Op::Jmp(off) => *off,
+            }
+        }
+    }
+
+    fn program(src: &str) -> Vec<Op> {
+        parse_program(src.lines()).unwrap()
+    }
+
+    #[test]
+    fn hook_sees_every_cycle_before_the_effect() {
+        let prog = program("noop\naddx 3\naddx -5\nnoop");
+        let mut machine = Machine::new(&prog, 1).with_trace();
+        let mut seen = Vec::new();
+        assert_eq!(machine.run_with(|cycle, x| seen.push((cycle, *x))), Ok(Halt::Finished));
+        assert_eq!(seen, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4), (6, -1)]);
+        assert_eq!((machine.cycles(), machine.pc(), *machine.state()), (6, 4, -1));
+        let trace: Vec<_> = machine.trace().iter().map(|t| (t.cycle, t.pc, t.state)).collect();
+        assert_eq!(trace, vec![(1, 0, 1), (2, 1, 4), (4, 2, -1), (6, 3, -1)]);
+        /* Once stopped, stepping runs nothing. */
+        assert_eq!(machine.step(), Ok(Some(Halt::Finished)));
+        assert_eq!(machine.cycles(), 6);
+    }
+
+    #[test]
+    fn loop_reports_the_repeated_instruction() {
+        let prog = program("nop +0\nacc +1\nacc +2\njmp -2");
+        let mut machine = Machine::new(&prog, 0);
+        assert_eq!(machine.run(), Ok(Halt::Loop(1)));
+        assert_eq!((machine.pc(), machine.into_state()), (1, 3));
+        let prog = program("jmp +0");
+        assert_eq!(Machine::new(&prog, 0).run(), Ok(Halt::Loop(0)));
+        assert_eq!(Machine::<Op>::new(&[], 0).run(), Ok(Halt::Finished));
+    }
+
+    #[test]
+    fn jumps_must_stay_in_the_program() {
+        let prog = program("jmp -1");
+        assert_eq!(Machine::new(&prog, 0).run(), Err(VmError::BadJump{from: 0, to: -1}));
+        let prog = program("nop\njmp +5\nnop");
+        assert_eq!(Machine::new(&prog, 0).run(), Err(VmError::BadJump{from: 1, to: 6}));
+        /* Just past the end is how a program finishes. */
+        let prog = program("jmp +3\nacc +1\nacc +1");
+        let mut machine = Machine::new(&prog, 0);
+        assert_eq!(machine.run(), Ok(Halt::Finished));
+        assert_eq!(*machine.state(), 0);
+    }
+
+    #[test]
+    fn parsing() {
+        let prog: Vec<Op> = parse_program(["", "noop", "   ", "acc +1", ""]).unwrap();
+        assert_eq!(prog, vec![Op::Nop(0), Op::Acc(1)]);
+        let err = parse_program::<Op, _>(["noop", "", "  bogus  "]).unwrap_err();
+        assert_eq!(err, VmError::Parse{line: 3, msg: "unknown instruction \"bogus\"".to_string()});
+        assert_eq!(err.to_string(), "line 3: unknown instruction \"bogus\"");
+        assert!(matches!(parse_program::<Op, _>(["acc x"]), Err(VmError::Parse{line: 1, ..})));
+    }
+
+    fn swap(op: &Op) -> Option<Op> {
+        match op {
+            Op::Nop(v) => Some(Op::Jmp(*v)),
+            Op::Jmp(v) => Some(Op::Nop(*v)),
+            _ => None,
+        }
+    }
+
+    fn fix(prog: &[Op]) -> Option<(usize, Op, isize)> {
+        repair(prog, 0, swap).unwrap().map(|r| (r.pc, r.instr, r.state))
+    }
+
+    #[test]
+    fn repairs() {
+        let example = program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6");
+        assert_eq!(fix(&example), Some((7, Op::Nop(-4), 8)));
+        /* The first instruction is the fix. */
+        assert_eq!(fix(&program("jmp +0\nacc +1")), Some((0, Op::Nop(0), 1)));
+        /* Already finishing, so nothing to repair. */
+        assert_eq!(fix(&program("acc +1\njmp +2\njmp -2")), None);
+        assert_eq!(fix(&[]), None);
+        /* Every swap on the path still loops. */
+        assert_eq!(fix(&program("nop +0\njmp -1\njmp -2")), None);
+        assert_eq!(fix(&program("acc +1\nacc +2\njmp -1")), Some((2, Op::Nop(-1), 3)));
+    }
+
+    #[test]
+    fn repairs_with_bad_jumps() {
+        /* Swapping the nop would jump out of the program, which just means it
+         * isn't the fix. */
+        assert_eq!(fix(&program("nop +5\njmp +0")), Some((1, Op::Nop(0), 0)));
+        /* A bad jump on the original path is an error, if nothing before it
+         * was the fix. */
+        let prog = program("nop +0\njmp -5");
+        assert_eq!(repair(&prog, 0, |_| None).unwrap_err(), VmError::BadJump{from: 1, to: -4});
+    }
+
+    /* Try every swap and rerun the whole program. The pcs which fix it
+     * should all be on the original path (the claim the shortcut in repair()
+     * rests on), and repair() should pick the first of them. */
+    #[test]
+    fn repair_matches_brute_force() {
+        let mut seed = 0x9e3779b97f4a7c15u64;
+        let mut next = |below: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % below
+        };
+        let mut found = 0;
+        for _ in 0..3000 {
+            let n = 1 + next(8) as isize;
+            let prog: Vec<Op> = (0..n).map(|pc| {
+                let off = next(n as u64 + 1) as isize - pc;
+                match next(3) {
+                    0 => Op::Nop(off),
+                    1 => Op::Acc(off),
+                    _ => Op::Jmp(off),
+                }
+            }).collect();
+            let mut machine = Machine::new(&prog, 0).with_trace();
+            if machine.run().unwrap() == Halt::Finished {
+                assert_eq!(fix(&prog), None, "{:?}", prog);
+                continue;
+            }
+            let path: Vec<usize> = machine.trace().iter().map(|t| t.pc).collect();
+            let mut fixes = Vec::new();
+            for (pc, op) in prog.iter().enumerate() {
+                let Some(swapped) = swap(op) else { continue };
+                let mut patched = prog.clone();
+                patched[pc] = swapped.clone();
+                let mut machine = Machine::new(&patched, 0);
+                if machine.run() == Ok(Halt::Finished) {
+                    assert!(path.contains(&pc), "fix at {} is off the path in {:?}", pc, prog);
+                    fixes.push((pc, swapped, machine.into_state()));
+                }
+            }
+            let first = path.iter().find_map(|pc| fixes.iter().find(|f| f.0 == *pc)).cloned();
+            found += first.is_some() as usize;
+            assert_eq!(fix(&prog), first, "{:?}", prog);
+        }
+        assert!(found > 100, "only {} repairable programs", found);
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::{HashSet, VecDeque};
+use std::ops::{Add, Neg, Sub};
+
+/// A point in N-dimensional integer space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PointN<const N: usize>(pub [isize; N]);
+
+pub type Point3 = PointN<3>;
+
+impl<const N: usize> PointN<N> {
+    pub const ORIGIN: Self = PointN([0; N]);
+
+    pub fn manhattan(&self, other: &Self) -> isize {
+        self.0.iter().zip(other.0.iter()).map(|(a, b)| (a - b).abs()).sum()
+    }
+
+    pub fn dot(&self, other: &Self) -> isize {
+        self.0.iter().zip(other.0.iter()).map(|(a, b)| a * b).sum()
+    }
+
+    pub fn scale(mut self, by: isize) -> Self {
+        for v in self.0.iter_mut() {
+            *v *= by;
+        }
+        self
+    }
+
+    /// The 2N points which share a face with this one (6 in 3D).
+    pub fn face_neighbours(&self) -> impl Iterator<Item = Self> + '_ {
+        face_offsets::<N>().map(move |d| *self + d)
+    }
+
+    /// The 3^N - 1 points which share at least a corner with this one (26 in 3D).
+    pub fn all_neighbours(&self) -> impl Iterator<Item = Self> + '_ {
+        all_offsets::<N>().map(move |d| *self + d)
+    }
+}
+
+impl Point3 {
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
+        PointN([x, y, z])
+    }
+}
+
+impl<const N: usize> Add for PointN<N> {
+    type Output = Self;
+    fn add(mut self, rhs: Self) -> Self {
+        for i in 0..N {
+            self.0[i] += rhs.0[i];
+        }
+        self
+    }
+}
+
+impl<const N: usize> Sub for PointN<N> {
+    type Output = Self;
+    fn sub(mut self, rhs: Self) -> Self {
+        for i in 0..N {
+            self.0[i] -= rhs.0[i];
+        }
+        self
+    }
+}
+
+impl<const N: usize> Neg for PointN<N> {
+    type Output = Self;
+    fn neg(mut self) -> Self {
+        for v in self.0.iter_mut() {
+            *v = -*v;
+        }
+        self
+    }
+}
+
+/// Unit steps along each axis, in both directions.
+pub fn face_offsets<const N: usize>() -> impl Iterator<Item = PointN<N>> {
+    (0..2 * N).map(|i| {
+        let mut p = [0; N];
+        p[i / 2] = if i % 2 == 0 { -1 } else { 1 };
+        PointN(p)
+    })
+}
+
+/// Every offset in {-1, 0, 1}^N except the origin.
+pub fn all_offsets<const N: usize>() -> impl Iterator<Item = PointN<N>> {
+    (0..3usize.pow(N as u32)).filter_map(|mut i| {
+        let mut p = [0; N];
+        for v in p.iter_mut() {
+            *v = (i % 3) as isize - 1;
+            i /= 3;
+        }
+        if p == [0; N] { None } else { Some(PointN(p)) }
+    })
+}
+
+/// An inclusive axis-aligned box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds<const N: usize> {
+    pub min: PointN<N>,
+    pub max: PointN<N>,
+}
+
+impl<const N: usize> Bounds<N> {
+    /// The smallest box containing every point, or None if there are none.
+    pub fn from_points<'a, I>(points: I) -> Option<Self>
+    where I: IntoIterator<Item = &'a PointN<N>> {
+        let mut iter = points.into_iter();
+        let first = *iter.next()?;
+        let mut bounds = Bounds{min: first, max: first};
+        for p in iter {
+            for i in 0..N {
+                bounds.min.0[i] = bounds.min.0[i].min(p.0[i]);
+                bounds.max.0[i] = bounds.max.0[i].max(p.0[i]);
+            }
+        }
+        Some(bounds)
+    }
+
+    /// Grow the box by `by` cells on every side.
+    pub fn expand(&self, by: isize) -> Self {
+        let delta = PointN([by; N]);
+        Bounds{min: self.min - delta, max: self.max + delta}
+    }
+
+    pub fn contains(&self, p: &PointN<N>) -> bool {
+        (0..N).all(|i| self.min.0[i] <= p.0[i] && p.0[i] <= self.max.0[i])
+    }
+
+    pub fn size(&self, axis: usize) -> usize {
+        (self.max.0[axis] - self.min.0[axis] + 1).max(0) as usize
+    }
+
+    pub fn volume(&self) -> usize {
+        (0..N).map(|i| self.size(i)).product()
+    }
+
+    /// Row-major index of a point within the box (last axis varies fastest).
+    pub fn index(&self, p: &PointN<N>) -> Option<usize> {
+        if !self.contains(p) {
+            return None;
+        }
+        let mut ix = 0;
+        for i in 0..N {
+            ix = ix * self.size(i) + (p.0[i] - self.min.0[i]) as usize;
+        }
+        Some(ix)
+    }
+
+    /// Inverse of `index()`.
+    pub fn point_at(&self, mut ix: usize) -> PointN<N> {
+        let mut p = self.min;
+        for i in (0..N).rev() {
+            let size = self.size(i);
+            p.0[i] += (ix % size) as isize;
+            ix /= size;
+        }
+        p
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = PointN<N>> + '_ {
+        (0..self.volume()).map(|ix| self.point_at(ix))
+    }
+}
+
+/// A dense grid of values covering a bounded region of N-dimensional space.
+#[derive(Clone, Debug)]
+pub struct VoxelGrid<const N: usize, T> {
+    pub bounds: Bounds<N>,
+    data: Vec<T>,
+}
+
+impl<const N: usize, T: Clone> VoxelGrid<N, T> {
+    pub fn new(bounds: Bounds<N>, fill: T) -> Self {
+        VoxelGrid{bounds, data: vec![fill; bounds.volume()]}
+    }
+}
+
+impl<const N: usize, T> VoxelGrid<N, T> {
+    pub fn in_bounds(&self, p: &PointN<N>) -> bool {
+        self.bounds.contains(p)
+    }
+
+    pub fn get(&self, p: &PointN<N>) -> Option<&T> {
+        self.bounds.index(p).map(|ix| &self.data[ix])
+    }
+
+    pub fn get_mut(&mut self, p: &PointN<N>) -> Option<&mut T> {
+        self.bounds.index(p).map(|ix| &mut self.data[ix])
+    }
+
+    /// Store a value, panicking if the point is out of bounds.
+    pub fn set(&mut self, p: &PointN<N>, val: T) {
+        match self.bounds.index(p) {
+            Some(ix) => self.data[ix] = val,
+            None => panic!("{:?}: index out of range (bounds: {:?})", p, self.bounds),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PointN<N>, &T)> + '_ {
+        self.data.iter().enumerate().map(|(ix, v)| (self.bounds.point_at(ix), v))
+    }
+}
+
+impl<const N: usize> VoxelGrid<N, bool> {
+    /// A grid spanning the points (plus `margin` cells of padding), with each point set.
+    pub fn from_points<'a, I>(points: I, margin: isize) -> Option<Self>
+    where I: IntoIterator<Item = &'a PointN<N>> + Clone {
+        let bounds = Bounds::from_points(points.clone())?.expand(margin);
+        let mut grid = VoxelGrid::new(bounds, false);
+        for p in points {
+            grid.set(p, true);
+        }
+        Some(grid)
+    }
+}
+
+/// Find every in-bounds cell reachable from `start` through face-adjacent
+/// steps, where `passable` decides which cells may be entered.
+pub fn flood_fill<const N: usize, F>(bounds: Bounds<N>, start: PointN<N>, passable: F) -> VoxelGrid<N, bool>
+where F: Fn(&PointN<N>) -> bool {
+    let mut seen = VoxelGrid::new(bounds, false);
+    if !bounds.contains(&start) || !passable(&start) {
+        return seen;
+    }
+    let mut q: VecDeque<PointN<N>> = VecDeque::new();
+    seen.set(&start, true);
+    q.push_back(start);
+    while let Some(cur) = q.pop_front() {
+        for next in cur.face_neighbours() {
+            if seen.get(&next) == Some(&false) && passable(&next) {
+                seen.set(&next, true);
+                q.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Count the faces of each point which are not shared with another point.
+pub fn surface_area<const N: usize>(points: &HashSet<PointN<N>>) -> usize {
+    points
+        .iter()
+        .flat_map(|p| p.face_neighbours().collect::<Vec<_>>())
+        .filter(|adj| !points.contains(adj))
+        .count()
+}
+
+/// Like `surface_area()`, but only count faces reachable from outside the
+/// shape, ignoring any enclosed air pockets.
+pub fn exterior_surface_area<const N: usize>(points: &HashSet<PointN<N>>) -> usize {
+    let solid = match VoxelGrid::from_points(points, 1) {
+        Some(grid) => grid,
+        None => return 0,
+    };
+    let outside = flood_fill(solid.bounds, solid.bounds.min, |p| solid.get(p) == Some(&false));
+    points
+        .iter()
+        .flat_map(|p| p.face_neighbours().collect::<Vec<_>>())
+        .filter(|adj| outside.get(adj) == Some(&true))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds3(min: [isize; 3], max: [isize; 3]) -> Bounds<3> {
+        Bounds{min: PointN(min), max: PointN(max)}
+    }
+
+    #[test]
+    fn bounds_from_points() {
+        let pts = [Point3::new(1, -2, 3), Point3::new(-1, 5, 0), Point3::new(0, 0, 0)];
+        let b = Bounds::from_points(&pts).unwrap();
+        assert_eq!(b, bounds3([-1, -2, 0], [1, 5, 3]));
+        assert_eq!((b.size(0), b.size(1), b.size(2)), (3, 8, 4));
+        assert_eq!(b.volume(), 96);
+        assert!(Bounds::<3>::from_points(&[]).is_none());
+    }
+
+    #[test]
+    fn bounds_single_point_and_expand() {
+        let b = Bounds::from_points(&[Point3::new(2, 2, 2)]).unwrap();
+        assert_eq!(b.volume(), 1);
+        let e = b.expand(1);
+        assert_eq!(e, bounds3([1, 1, 1], [3, 3, 3]));
+        assert_eq!(e.volume(), 27);
+        assert!(e.contains(&Point3::new(1, 3, 2)));
+        assert!(!e.contains(&Point3::new(0, 2, 2)));
+    }
+
+    #[test]
+    fn bounds_empty_box() {
+        /* min > max on an axis is an empty box, not a negative size. */
+        let b = bounds3([0, 0, 0], [-1, 3, 3]);
+        assert_eq!(b.size(0), 0);
+        assert_eq!(b.volume(), 0);
+        assert_eq!(b.points().count(), 0);
+    }
+
+    #[test]
+    fn index_round_trips() {
+        let b = bounds3([-2, 0, 5], [1, 2, 6]);
+        let mut seen = vec![false; b.volume()];
+        for p in b.points() {
+            let ix = b.index(&p).unwrap();
+            assert!(!seen[ix]);
+            seen[ix] = true;
+            assert_eq!(b.point_at(ix), p);
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert_eq!(b.index(&Point3::new(2, 0, 5)), None);
+        /* Last axis varies fastest. */
+        assert_eq!(b.point_at(1), Point3::new(-2, 0, 6));
+    }
+
+    #[test]
+    fn flood_fill_stops_at_walls() {
+        /* A hollow 3x3x3 shell: the centre is unreachable from outside. */
+        let shell: HashSet<Point3> = bounds3([0, 0, 0], [2, 2, 2])
+            .points()
+            .filter(|p| *p != Point3::new(1, 1, 1))
+            .collect();
+        let b = bounds3([-1, -1, -1], [3, 3, 3]);
+        let outside = flood_fill(b, b.min, |p| !shell.contains(p));
+        assert_eq!(outside.iter().filter(|(_, v)| **v).count(), 125 - 27);
+        assert_eq!(outside.get(&Point3::new(1, 1, 1)), Some(&false));
+        let inside = flood_fill(b, Point3::new(1, 1, 1), |p| !shell.contains(p));
+        assert_eq!(inside.iter().filter(|(_, v)| **v).count(), 1);
+    }
+
+    #[test]
+    fn flood_fill_blocked_or_out_of_bounds_start() {
+        let b = bounds3([0, 0, 0], [2, 2, 2]);
+        let none = flood_fill(b, b.min, |_| false);
+        assert!(none.iter().all(|(_, v)| !*v));
+        let outside = flood_fill(b, Point3::new(5, 5, 5), |_| true);
+        assert!(outside.iter().all(|(_, v)| !*v));
+        let all = flood_fill(b, b.min, |_| true);
+        assert!(all.iter().all(|(_, v)| *v));
+    }
+
+    #[test]
+    fn flood_fill_2d() {
+        /* A wall down x = 2 with a gap at y = 4. */
+        let b = Bounds{min: PointN([0, 0]), max: PointN([4, 4])};
+        let wall = |p: &PointN<2>| p.0[0] == 2 && p.0[1] != 4;
+        let filled = flood_fill(b, PointN([0, 0]), |p| !wall(p));
+        assert_eq!(filled.get(&PointN([4, 0])), Some(&true));
+        let sealed = flood_fill(b, PointN([0, 0]), |p| p.0[0] != 2);
+        assert_eq!(sealed.get(&PointN([4, 0])), Some(&false));
+        assert_eq!(sealed.iter().filter(|(_, v)| **v).count(), 10);
+    }
+
+    #[test]
+    fn surface_areas() {
+        let two: HashSet<Point3> = [Point3::new(1, 1, 1), Point3::new(2, 1, 1)].into_iter().collect();
+        assert_eq!(surface_area(&two), 10);
+        assert_eq!(exterior_surface_area(&two), 10);
+        let shell: HashSet<Point3> = bounds3([0, 0, 0], [2, 2, 2])
+            .points()
+            .filter(|p| *p != Point3::new(1, 1, 1))
+            .collect();
+        assert_eq!(surface_area(&shell), 54 + 6);
+        assert_eq!(exterior_surface_area(&shell), 54);
+        assert_eq!(exterior_surface_area(&HashSet::<Point3>::new()), 0);
+    }
+}